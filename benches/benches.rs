@@ -9,8 +9,8 @@ use bevy_math::{
 };
 use bevy_mesh::{Mesh, skinning::SkinnedMeshInverseBindposes};
 use bevy_mod_skinned_aabb::{
-    PackedAabb3d, SkinnedAabbPluginSettings, aabb_transformed_by, create_skinned_aabbs,
-    update_skinned_aabbs,
+    PackedAabb3d, SkinnedAabbBatching, SkinnedAabbPluginSettings, aabb_transformed_by,
+    create_skinned_aabbs, update_skinned_aabbs,
 };
 use bevy_transform::prelude::*;
 use core::time::Duration;
@@ -235,7 +235,13 @@ pub fn systems(c: &mut Criterion) {
 
     let num_assets = 10;
 
-    for parallel in [false, true] {
+    let modes = [
+        (false, SkinnedAabbBatching::Default),
+        (true, SkinnedAabbBatching::Default),
+        (true, SkinnedAabbBatching::Joints(1024)),
+    ];
+
+    for (parallel, batching) in modes {
         for &Combo {
             num_joints_total,
             num_meshes,
@@ -267,8 +273,8 @@ pub fn systems(c: &mut Criterion) {
             }
 
             let name = format!(
-                "(parallel = {}, assets = {}, joints total = {}, joints per mesh = {}, meshes = {})",
-                parallel, num_assets, num_joints_total, num_joints, num_meshes,
+                "(parallel = {}, batching = {:?}, assets = {}, joints total = {}, joints per mesh = {}, meshes = {})",
+                parallel, batching, num_assets, num_joints_total, num_joints, num_meshes,
             );
 
             let mesh_params = MeshParams {
@@ -277,7 +283,11 @@ pub fn systems(c: &mut Criterion) {
                 num_joints,
             };
 
-            let settings = SkinnedAabbPluginSettings { parallel };
+            let settings = SkinnedAabbPluginSettings {
+                parallel,
+                batching,
+                ..Default::default()
+            };
 
             group.bench_function(name, |b| systems_internal(b, settings, &mesh_params));
         }
@@ -22,7 +22,7 @@ use bevy_ecs::{
     world::World,
 };
 use bevy_math::{
-    Affine3A, Mat4, Quat, Vec3,
+    Mat4, Quat, Vec3,
     curve::{Curve, EaseFunction, EasingCurve},
     ops,
 };
@@ -31,7 +31,8 @@ use bevy_mesh::{
     skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
 };
 use bevy_mod_skinned_aabb::{
-    JointIndex, MAX_INFLUENCES, SkinnedAabbAsset, SkinnedAabbPluginSettings,
+    JointIndex, MAX_INFLUENCES, SkinnedAabbAsset, SkinnedAabbDiagnostics, SkinnedAabbInfluences,
+    SkinnedAabbJointPadding, SkinnedAabbPluginSettings,
 };
 use bevy_transform::components::{GlobalTransform, Transform};
 use rand::{
@@ -158,12 +159,14 @@ pub enum RandomMeshError {
 }
 
 // Create a mesh with random triangles skinned to random joints with varying
-// weights.
+// weights. If `normalized` is false then the sum of each vertex's weights will
+// be randomly scaled.
 fn create_random_soft_skinned_mesh(
     rng: &mut impl Rng,
     num_tris: usize,
     num_unskinned_joints: usize,
     num_skinned_joints: usize,
+    normalized: bool,
 ) -> Result<Mesh, RandomMeshError> {
     let num_joints = JointIndex::try_from(num_unskinned_joints + num_skinned_joints)
         .or(Err(RandomMeshError::InvalidNumJoints))?;
@@ -172,6 +175,7 @@ fn create_random_soft_skinned_mesh(
     let joint_index_dist = Uniform::new(num_unskinned_joints as JointIndex, num_joints);
     let joint_weight_dist = Uniform::new(0.01, 1.0);
     let num_influences_dist = Uniform::new_inclusive(1, MAX_INFLUENCES);
+    let weight_sum_dist = Uniform::new_inclusive(0.8, 1.2);
 
     let num_verts = num_tris * 3;
 
@@ -190,7 +194,13 @@ fn create_random_soft_skinned_mesh(
             vert_joint_weights[influence_index] = rng.sample(joint_weight_dist);
         }
 
-        let normalization_scale = 1.0 / vert_joint_weights.iter().sum::<f32>();
+        let weight_sum = if normalized {
+            1.0
+        } else {
+            rng.sample(weight_sum_dist)
+        };
+
+        let normalization_scale = weight_sum / vert_joint_weights.iter().sum::<f32>();
         let vert_joint_weights = vert_joint_weights.map(|w| w * normalization_scale);
 
         positions[vert_index] = position;
@@ -282,6 +292,7 @@ pub struct SkinnedMeshAssets {
 pub enum RandomSkinnedMeshType {
     Hard,
     Soft { num_tris: usize },
+    SoftUnnormalized { num_tris: usize },
 }
 
 pub fn create_random_skinned_mesh_assets(
@@ -295,9 +306,20 @@ pub fn create_random_skinned_mesh_assets(
     let num_joints = num_unskinned_joints + num_skinned_joints;
 
     let mesh = match mesh_type {
-        RandomSkinnedMeshType::Soft { num_tris } => {
-            create_random_soft_skinned_mesh(rng, num_tris, num_unskinned_joints, num_skinned_joints)
-        }
+        RandomSkinnedMeshType::Soft { num_tris } => create_random_soft_skinned_mesh(
+            rng,
+            num_tris,
+            num_unskinned_joints,
+            num_skinned_joints,
+            true,
+        ),
+        RandomSkinnedMeshType::SoftUnnormalized { num_tris } => create_random_soft_skinned_mesh(
+            rng,
+            num_tris,
+            num_unskinned_joints,
+            num_skinned_joints,
+            false,
+        ),
         RandomSkinnedMeshType::Hard => {
             create_random_hard_skinned_mesh(rng, num_unskinned_joints, num_skinned_joints)
         }
@@ -556,6 +578,9 @@ pub fn create_dev_world(settings: SkinnedAabbPluginSettings) -> World {
     world.init_resource::<Assets<Mesh>>();
     world.init_resource::<Assets<SkinnedMeshInverseBindposes>>();
     world.init_resource::<Assets<SkinnedAabbAsset>>();
+    world.init_resource::<SkinnedAabbInfluences>();
+    world.init_resource::<SkinnedAabbJointPadding>();
+    world.init_resource::<SkinnedAabbDiagnostics>();
     world.init_resource::<Assets<StandardMaterial>>();
 
     world.insert_resource(settings);
@@ -584,7 +609,8 @@ fn skin_positions(
     positions: &VertexAttributeValues,
     joint_indices: &[[u16; 4]],
     joint_weights: &[[f32; 4]],
-    entity_from_binds: &[Mat4],
+    world_from_binds: &[Mat4],
+    entity_from_world: Mat4,
 ) -> Result<Vec<[f32; 3]>, SkinError> {
     let VertexAttributeValues::Float32x3(positions) = positions else {
         return Err(SkinError::UnexpectedPositionAttributeType);
@@ -604,19 +630,21 @@ fn skin_positions(
         let vertex_joint_indices = joint_indices[vertex_index];
         let vertex_joint_weights = joint_weights[vertex_index];
 
-        let mut weighted_entity_from_binds = [Mat4::ZERO; 4];
+        let mut weighted_world_from_binds = [Mat4::ZERO; 4];
 
         for influence_index in 0..4 {
             let joint_weight = vertex_joint_weights[influence_index];
             let joint_index = vertex_joint_indices[influence_index] as usize;
-            let entity_from_bind = *entity_from_binds
+            let world_from_bind = *world_from_binds
                 .get(joint_index)
                 .ok_or(SkinError::InvalidJointIndex)?;
 
-            weighted_entity_from_binds[influence_index] = joint_weight * entity_from_bind;
+            weighted_world_from_binds[influence_index] = joint_weight * world_from_bind;
         }
 
-        let entity_from_bind = weighted_entity_from_binds.iter().sum::<Mat4>();
+        // Blend in world space to match the GPU. This only matters if the
+        // weights are not normalized.
+        let entity_from_bind = entity_from_world * weighted_world_from_binds.iter().sum::<Mat4>();
 
         let skinned_position =
             <[f32; 3]>::from(entity_from_bind.transform_point3(Vec3::from_slice(position)));
@@ -630,9 +658,10 @@ fn skin_positions(
 fn skin_internal(
     mesh: &Mesh,
     inverse_bindposes: &[Mat4],
-    entity_from_joints: &[Mat4],
+    world_from_joints: &[Mat4],
+    entity_from_world: Mat4,
 ) -> Result<Mesh, SkinError> {
-    if entity_from_joints.len() != inverse_bindposes.len() {
+    if world_from_joints.len() != inverse_bindposes.len() {
         return Err(SkinError::MismatchedJointAndInverseBindposesLengths);
     }
 
@@ -648,10 +677,10 @@ fn skin_internal(
         return Err(SkinError::UnexpectedJointWeightsAttributeType);
     };
 
-    let entity_from_binds = entity_from_joints
+    let world_from_binds = world_from_joints
         .iter()
         .zip(inverse_bindposes.iter())
-        .map(|(entity_from_joint, inverse_bindpose)| *entity_from_joint * *inverse_bindpose)
+        .map(|(world_from_joint, inverse_bindpose)| *world_from_joint * *inverse_bindpose)
         .collect::<Vec<_>>();
 
     // TODO: Awkward? Appears needed since match patterns can't be expressions.
@@ -669,7 +698,13 @@ fn skin_internal(
             POSITION_ID => {
                 out.insert_attribute(
                     *attribute,
-                    skin_positions(values, joint_indices, joint_weights, &entity_from_binds)?,
+                    skin_positions(
+                        values,
+                        joint_indices,
+                        joint_weights,
+                        &world_from_binds,
+                        entity_from_world,
+                    )?,
                 );
             }
 
@@ -680,14 +715,8 @@ fn skin_internal(
     Ok(out)
 }
 
-fn try_entity_from_joint(
-    joints: &Query<&GlobalTransform>,
-    entity: Entity,
-    entity_from_world: Affine3A,
-) -> Option<Mat4> {
-    let world_from_joint = joints.get(entity).ok()?.affine();
-
-    Some(Mat4::from(entity_from_world * world_from_joint))
+fn try_world_from_joint(joints: &Query<&GlobalTransform>, entity: Entity) -> Option<Mat4> {
+    Some(Mat4::from(joints.get(entity).ok()?.affine()))
 }
 
 // Given the components of a skinned mesh, return a copy of the mesh with
@@ -703,10 +732,10 @@ pub fn skin(
 ) -> Result<Mesh, SkinError> {
     let entity_from_world = world_from_entity.affine().inverse();
 
-    let entity_from_joints = skinned_mesh
+    let world_from_joints = skinned_mesh
         .joints
         .iter()
-        .map(|&entity| try_entity_from_joint(joint_transforms, entity, entity_from_world))
+        .map(|&entity| try_world_from_joint(joint_transforms, entity))
         .collect::<Option<Vec<_>>>()
         .ok_or(SkinError::MissingJointEntity)?;
 
@@ -718,5 +747,10 @@ pub fn skin(
         .get(&skinned_mesh.inverse_bindposes)
         .ok_or(SkinError::MissingInverseBindposesAsset)?;
 
-    skin_internal(mesh_asset, inverse_bindposes_asset, &entity_from_joints)
+    skin_internal(
+        mesh_asset,
+        inverse_bindposes_asset,
+        &world_from_joints,
+        Mat4::from(entity_from_world),
+    )
 }
@@ -5,13 +5,13 @@ use bevy::{
         PrimitiveTopology, VertexAttributeValues,
         skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
     },
-    picking::{backend::ray::RayMap, mesh_picking::ray_cast::ray_aabb_intersection_3d},
+    picking::backend::ray::RayMap,
     prelude::*,
     scene::SceneInstanceReady,
 };
 use bevy_camera::primitives::Aabb;
 use bevy_math::{Affine3A, bounding::Aabb3d};
-use bevy_mod_skinned_aabb::{SkinnedAabb, SkinnedAabbAsset, prelude::*};
+use bevy_mod_skinned_aabb::{prelude::*, raycast::SkinnedAabbRaycast};
 use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
 
 fn main() {
@@ -380,34 +380,19 @@ fn gizmo_transform_from_aabb3d(aabb: Aabb3d) -> Affine3A {
     gizmo_transform_from_aabb(Aabb::from_min_max(aabb.min.into(), aabb.max.into()))
 }
 
-fn raycast(
-    query: Query<(&SkinnedAabb, &SkinnedMesh)>,
-    joints: Query<&GlobalTransform>,
-    mut gizmos: Gizmos,
-    assets: Res<Assets<SkinnedAabbAsset>>,
-    ray_map: Res<RayMap>,
-) {
+fn raycast(raycast: SkinnedAabbRaycast, mut gizmos: Gizmos, ray_map: Res<RayMap>) {
     for (_, ray) in ray_map.iter() {
-        query.iter().for_each(|(skinned_aabb, skinned_mesh)| {
-            if let Some(asset) = assets.get(&skinned_aabb.asset) {
-                for aabb_index in 0..asset.num_aabbs() {
-                    if let Some(world_from_joint) =
-                        asset.world_from_joint(aabb_index, skinned_mesh, &joints)
-                        && ray_aabb_intersection_3d(
-                            *ray,
-                            &asset.aabb(aabb_index).into(),
-                            &world_from_joint,
-                        )
-                        .is_some()
-                    {
-                        let joint_from_aabb =
-                            gizmo_transform_from_aabb3d(asset.aabb(aabb_index).into());
-                        let world_from_aabb = world_from_joint * joint_from_aabb;
-
-                        gizmos.cube(world_from_aabb, Color::WHITE);
-                    }
-                }
+        for hit in raycast.cast_ray(*ray) {
+            if let Some(bound) = raycast
+                .joint_bounds
+                .iter(hit.entity)
+                .find(|bound| bound.joint_index == hit.joint_index)
+            {
+                let joint_from_aabb = gizmo_transform_from_aabb3d(bound.aabb.into());
+                let world_from_aabb = bound.world_from_joint * joint_from_aabb;
+
+                gizmos.cube(world_from_aabb, Color::WHITE);
             }
-        })
+        }
     }
 }
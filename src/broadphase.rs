@@ -0,0 +1,353 @@
+use bevy_camera::primitives::{Aabb, Frustum};
+use bevy_ecs::{
+    change_detection::ResMut,
+    entity::{Entity, EntityHashSet},
+    query::With,
+    resource::Resource,
+    system::Query,
+};
+use bevy_math::{
+    Affine3A, IVec3, Ray3d, Vec3, Vec3A,
+    bounding::{Aabb3d, BoundingSphere, IntersectsVolume},
+};
+use bevy_platform::collections::HashMap;
+
+use crate::{
+    SkinnedAabb, entity_world_aabb,
+    raycast::{EntityBoundsData, ray_box_distance},
+};
+
+// AABBs that would be added to more than this many cells are kept in a
+// separate list that every query checks.
+const MAX_CELLS_PER_ENTRY: i64 = 64;
+
+// Return the number of cells in the inclusive range `min..=max`. Huge AABBs can
+// span the whole range of cell coordinates, so this can't overflow.
+fn num_cells(min: IVec3, max: IVec3) -> i64 {
+    let size = max.as_i64vec3() - min.as_i64vec3() + 1;
+
+    size.x.saturating_mul(size.y).saturating_mul(size.z)
+}
+
+// A uniform grid of the world-space AABBs of entities with `SkinnedAabb`.
+//
+// The plugin rebuilds the grid after updating the entities' `Aabb`, but only
+// if this resource exists. Insert it to enable the broadphase.
+#[derive(Resource, Debug)]
+pub struct SkinnedAabbBroadphase {
+    cell_size: f32,
+
+    // World-space AABB of each entity.
+    entries: Vec<(Entity, Aabb3d)>,
+
+    // Indices into `entries` of the AABBs that touch each cell.
+    cells: HashMap<IVec3, Vec<usize>>,
+
+    // Indices into `entries` of the AABBs that touch too many cells.
+    oversized: Vec<usize>,
+
+    // AABB of all the entries that are in `cells`.
+    bounds: Option<Aabb3d>,
+}
+
+impl Default for SkinnedAabbBroadphase {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl SkinnedAabbBroadphase {
+    // Create an empty broadphase. The best `cell_size` is usually a bit larger
+    // than a typical character.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Cell size must be positive.");
+
+        SkinnedAabbBroadphase {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::default(),
+            oversized: Vec::new(),
+            bounds: None,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    // Return every entity and its world-space AABB.
+    pub fn entries(&self) -> &[(Entity, Aabb3d)] {
+        &self.entries
+    }
+
+    fn cell(&self, point: Vec3A) -> IVec3 {
+        (Vec3::from(point) / self.cell_size).floor().as_ivec3()
+    }
+
+    fn cell_range(&self, aabb: Aabb3d) -> (IVec3, IVec3) {
+        (self.cell(aabb.min), self.cell(aabb.max))
+    }
+
+    // Replace the contents with the given entities and world-space AABBs.
+    pub fn rebuild(&mut self, entries: impl IntoIterator<Item = (Entity, Aabb3d)>) {
+        self.entries.clear();
+        self.cells.values_mut().for_each(Vec::clear);
+        self.oversized.clear();
+        self.bounds = None;
+
+        self.entries.extend(
+            entries
+                .into_iter()
+                .filter(|(_, aabb)| aabb.min.is_finite() && aabb.max.is_finite()),
+        );
+
+        for (index, &(_, aabb)) in self.entries.iter().enumerate() {
+            let (min, max) = self.cell_range(aabb);
+
+            if num_cells(min, max) > MAX_CELLS_PER_ENTRY {
+                self.oversized.push(index);
+                continue;
+            }
+
+            for z in min.z..=max.z {
+                for y in min.y..=max.y {
+                    for x in min.x..=max.x {
+                        self.cells
+                            .entry(IVec3::new(x, y, z))
+                            .or_default()
+                            .push(index);
+                    }
+                }
+            }
+
+            self.bounds = Some(match self.bounds {
+                Some(bounds) => Aabb3d {
+                    min: bounds.min.min(aabb.min),
+                    max: bounds.max.max(aabb.max),
+                },
+                None => aabb,
+            });
+        }
+
+        // Drop cells that were emptied so the map doesn't grow forever.
+        self.cells.retain(|_, indices| !indices.is_empty());
+    }
+
+    // Call `f` once for each entry that might touch `aabb`.
+    fn for_each_candidate(&self, aabb: Aabb3d, mut f: impl FnMut(Entity, Aabb3d)) {
+        let mut seen = EntityHashSet::default();
+        let mut visit = |index: usize| {
+            let (entity, entry_aabb) = self.entries[index];
+
+            if seen.insert(entity) {
+                f(entity, entry_aabb);
+            }
+        };
+
+        self.oversized.iter().for_each(|&index| visit(index));
+
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+
+        // Clamp to the bounds of the occupied cells, which skips queries that
+        // miss all of them.
+        let clamped = Aabb3d {
+            min: aabb.min.max(bounds.min),
+            max: aabb.max.min(bounds.max),
+        };
+
+        if clamped.min.cmpgt(clamped.max).any() {
+            return;
+        }
+
+        let (min, max) = self.cell_range(clamped);
+
+        // The clamped range can still cover far more cells than are occupied,
+        // for example if the entries are spread out. Visit whichever is fewer.
+        if num_cells(min, max) > self.cells.len() as i64 {
+            for (cell, indices) in &self.cells {
+                if cell.cmpge(min).all() && cell.cmple(max).all() {
+                    indices.iter().for_each(|&index| visit(index));
+                }
+            }
+
+            return;
+        }
+
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    if let Some(indices) = self.cells.get(&IVec3::new(x, y, z)) {
+                        indices.iter().for_each(|&index| visit(index));
+                    }
+                }
+            }
+        }
+    }
+
+    // Return the entities whose AABB intersects `aabb`.
+    pub fn query_aabb(&self, aabb: Aabb3d) -> Vec<Entity> {
+        let mut result = Vec::new();
+
+        self.for_each_candidate(aabb, |entity, entry_aabb| {
+            if entry_aabb.intersects(&aabb) {
+                result.push(entity);
+            }
+        });
+
+        result
+    }
+
+    // Return the entities whose AABB intersects `sphere`.
+    pub fn query_sphere(&self, sphere: BoundingSphere) -> Vec<Entity> {
+        let radius = Vec3A::splat(sphere.radius());
+        let aabb = Aabb3d {
+            min: sphere.center - radius,
+            max: sphere.center + radius,
+        };
+
+        let mut result = Vec::new();
+
+        self.for_each_candidate(aabb, |entity, entry_aabb| {
+            if entry_aabb.intersects(&sphere) {
+                result.push(entity);
+            }
+        });
+
+        result
+    }
+
+    // Return the entities whose AABB intersects `frustum`.
+    //
+    // Unlike the other queries this doesn't use the grid. It tests every entry,
+    // so the cost is linear in the number of entries however small the frustum
+    // is. Frustums usually cover most of the occupied cells, so the grid
+    // wouldn't save much.
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<Entity> {
+        self.entries
+            .iter()
+            .filter(|(_, aabb)| {
+                frustum.intersects_obb_identity(&Aabb::from_min_max(
+                    Vec3::from(aabb.min),
+                    Vec3::from(aabb.max),
+                ))
+            })
+            .map(|&(entity, _)| entity)
+            .collect()
+    }
+
+    // Return the entities whose AABB is hit by `ray` within `max_distance`,
+    // along with the distance to the hit, sorted by distance.
+    pub fn query_ray(&self, ray: Ray3d, max_distance: f32) -> Vec<(Entity, f32)> {
+        let ray_distance = |aabb: Aabb3d| {
+            ray_box_distance(ray, aabb, Affine3A::IDENTITY)
+                .filter(|&distance| distance <= max_distance)
+        };
+
+        let mut result = Vec::new();
+        let mut test = |entity: Entity, aabb: Aabb3d| {
+            if let Some(distance) = ray_distance(aabb) {
+                result.push((entity, distance));
+            }
+        };
+
+        let mut seen = EntityHashSet::default();
+
+        for &index in &self.oversized {
+            let (entity, aabb) = self.entries[index];
+
+            if seen.insert(entity) {
+                test(entity, aabb);
+            }
+        }
+
+        // Walk the cells along the part of the ray that's inside the occupied
+        // cells, using "A Fast Voxel Traversal Algorithm for Ray Tracing",
+        // Amanatides and Woo (1987).
+
+        let Some(bounds) = self.bounds else {
+            result.sort_by(|l, r| l.1.total_cmp(&r.1));
+            return result;
+        };
+
+        if let Some(start) = ray_distance(bounds) {
+            let origin = Vec3A::from(ray.origin);
+            let direction = Vec3A::from(*ray.direction);
+
+            // The ray can't travel further than the diagonal while it's inside
+            // the occupied cells.
+            let end = max_distance.min(start + (bounds.max - bounds.min).length());
+
+            let mut cell = self.cell(origin + direction * start);
+            let last = self.cell(origin + direction * end);
+
+            let step = direction.signum().as_ivec3();
+            let delta = Vec3A::splat(self.cell_size) / direction.abs();
+
+            let next_boundary = (cell.as_vec3a()
+                + Vec3A::select(direction.cmpgt(Vec3A::ZERO), Vec3A::ONE, Vec3A::ZERO))
+                * self.cell_size;
+
+            // Distance along the ray to the next boundary on each axis. Axes
+            // that the ray doesn't move along are never crossed.
+            let mut next = Vec3A::select(
+                direction.cmpeq(Vec3A::ZERO),
+                Vec3A::INFINITY,
+                (next_boundary - origin) / direction,
+            );
+
+            loop {
+                if let Some(indices) = self.cells.get(&cell) {
+                    for &index in indices {
+                        let (entity, aabb) = self.entries[index];
+
+                        if seen.insert(entity) {
+                            test(entity, aabb);
+                        }
+                    }
+                }
+
+                if cell == last {
+                    break;
+                }
+
+                let axis = if next.x < next.y && next.x < next.z {
+                    0
+                } else if next.y < next.z {
+                    1
+                } else {
+                    2
+                };
+
+                if next[axis] > end {
+                    break;
+                }
+
+                cell[axis] += step[axis];
+                next[axis] += delta[axis];
+            }
+        }
+
+        result.sort_by(|l, r| l.1.total_cmp(&r.1));
+        result
+    }
+}
+
+// Rebuild the `SkinnedAabbBroadphase` from the world-space AABBs of entities
+// with `SkinnedAabb`. Uses `GlobalSkinnedAabb` if the entity has it, since
+// that's tighter than transforming the `Aabb`. Entities with neither are left
+// out.
+pub fn update_skinned_aabb_broadphase(
+    mut broadphase: ResMut<SkinnedAabbBroadphase>,
+    query: Query<EntityBoundsData, With<SkinnedAabb>>,
+) {
+    broadphase.rebuild(query.iter().filter_map(
+        |(entity, aabb, global_aabb, world_from_entity)| {
+            Some((
+                entity,
+                entity_world_aabb(aabb, global_aabb, world_from_entity)?,
+            ))
+        },
+    ));
+}
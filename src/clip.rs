@@ -0,0 +1,333 @@
+use bevy_animation::{
+    AnimationClip, AnimationPlayer, animate_targets,
+    graph::{AnimationGraph, AnimationGraphHandle, AnimationNodeIndex, AnimationNodeType},
+};
+use bevy_asset::{AssetId, Assets};
+use bevy_camera::primitives::Aabb;
+use bevy_ecs::{
+    change_detection::{Res, ResMut},
+    component::Component,
+    entity::Entity,
+    hierarchy::Children,
+    query::With,
+    system::{IntoSystem, Query, RunSystemOnce, SystemState},
+    world::World,
+};
+use bevy_math::{
+    Vec3, Vec3A,
+    bounding::{Aabb3d, BoundingVolume},
+};
+use bevy_mesh::skinning::SkinnedMesh;
+use bevy_platform::collections::HashMap;
+use bevy_transform::{
+    components::GlobalTransform,
+    systems::{mark_dirty_trees, propagate_parent_transforms, sync_simple_transforms},
+};
+
+use crate::{
+    PackedAabb3d, SkinnedAabb, SkinnedAabbAsset, SkinnedAabbDiagnostics, SkinnedAabbIssue,
+    SkinnedAabbPadding, SkinnedAabbPluginSettings, aabb_transformed_by, create_skinned_aabbs,
+    entity_from_world, entity_padding, get_skinned_aabb_in_space,
+};
+
+// Precomputed bounds of a skinned mesh for each animation clip it plays.
+//
+// Entities with this component get their `Aabb` from the bounds of whichever
+// clips the player is currently playing, instead of from their joints. This is
+// much cheaper than `update_skinned_aabbs`, but is only correct if the entity
+// plays the clips without modification. Blending between clips uses the union
+// of each clip's bounds, which is usually but not always conservative.
+//
+// The baked bounds include any joint padding. The entity's padding is added
+// when the `Aabb` is updated, so it can change after baking.
+//
+// Only the `Aabb` is updated, and `SkinnedAabbHysteresis` isn't applied since
+// the clip bounds rarely change. `GlobalSkinnedAabb`, `SweptSkinnedAabb` and
+// the targets of `SkinnedBoundsPlugin` are left alone.
+#[derive(Component, Clone, Debug)]
+pub struct SkinnedAabbClipBounds {
+    // The entity with the `AnimationPlayer` that plays the clips. The bounds
+    // are in the space of this entity.
+    pub player: Entity,
+
+    // Player-space AABB of the mesh for each clip, without the entity's
+    // padding.
+    pub clips: HashMap<AssetId<AnimationClip>, PackedAabb3d>,
+}
+
+#[derive(Debug)]
+pub enum SkinnedAabbClipBakeError {
+    MissingPlayer,
+    MissingGraph,
+    MissingClip,
+    FailedToRunSystem,
+}
+
+fn run_system<M>(
+    world: &mut World,
+    system: impl IntoSystem<(), (), M>,
+) -> Result<(), SkinnedAabbClipBakeError> {
+    world
+        .run_system_once(system)
+        .or(Err(SkinnedAabbClipBakeError::FailedToRunSystem))
+}
+
+// The AABBs of the skinned meshes that are descendants of `player`, in the
+// space of `player`.
+fn skinned_aabbs_in_player_space(
+    world: &mut World,
+    player: Entity,
+    meshes: &[Entity],
+) -> Vec<Option<Aabb3d>> {
+    let mut state = SystemState::<(
+        Query<(&SkinnedAabb, &SkinnedMesh, &GlobalTransform)>,
+        Query<&GlobalTransform>,
+        Res<Assets<SkinnedAabbAsset>>,
+        Res<SkinnedAabbPluginSettings>,
+    )>::new(world);
+
+    let (query, joints, assets, settings) = state.get(world);
+
+    let Ok(world_from_player) = joints.get(player) else {
+        return vec![None; meshes.len()];
+    };
+
+    let player_from_world = world_from_player.affine().inverse();
+
+    if !player_from_world.is_finite() {
+        return vec![None; meshes.len()];
+    }
+
+    meshes
+        .iter()
+        .map(|&mesh| {
+            let (skinned_aabb, skinned_mesh, world_from_entity) = query.get(mesh).ok()?;
+
+            get_skinned_aabb_in_space(
+                skinned_aabb,
+                &joints,
+                &assets,
+                skinned_mesh,
+                world_from_entity,
+                player_from_world,
+                0,
+                settings.conservative_rounding,
+                &mut None,
+            )
+        })
+        .collect()
+}
+
+// Return each animation clip node in the player's graph along with the clip's
+// duration.
+fn clip_nodes(
+    world: &World,
+    player: Entity,
+) -> Result<Vec<(AnimationNodeIndex, AssetId<AnimationClip>, f32)>, SkinnedAabbClipBakeError> {
+    let graph_handle = world
+        .get::<AnimationGraphHandle>(player)
+        .ok_or(SkinnedAabbClipBakeError::MissingGraph)?;
+
+    let graph = world
+        .resource::<Assets<AnimationGraph>>()
+        .get(&graph_handle.0)
+        .ok_or(SkinnedAabbClipBakeError::MissingGraph)?;
+
+    let clips = world.resource::<Assets<AnimationClip>>();
+
+    graph
+        .nodes()
+        .filter_map(|node_index| match &graph.get(node_index)?.node_type {
+            AnimationNodeType::Clip(handle) => Some((node_index, handle)),
+            _ => None,
+        })
+        .map(|(node_index, handle)| {
+            let clip = clips
+                .get(handle)
+                .ok_or(SkinnedAabbClipBakeError::MissingClip)?;
+
+            Ok((node_index, handle.id(), clip.duration()))
+        })
+        .collect()
+}
+
+// Sample each clip in the animation graph of `player` and insert a
+// `SkinnedAabbClipBounds` into every skinned mesh that's a descendant of
+// `player`.
+//
+// This is intended to run once after a character has been spawned and its
+// assets have loaded, for example during a loading screen. The player's
+// playing animations are restored afterwards, but the joint transforms will
+// be left in the pose of the last sample until the next animation update.
+pub fn bake_skinned_aabb_clip_bounds(
+    world: &mut World,
+    player: Entity,
+    samples_per_second: f32,
+) -> Result<(), SkinnedAabbClipBakeError> {
+    let saved_player = world
+        .get::<AnimationPlayer>(player)
+        .ok_or(SkinnedAabbClipBakeError::MissingPlayer)?
+        .clone();
+
+    let clip_nodes = clip_nodes(world, player)?;
+
+    // Make sure the skinned meshes have their `SkinnedAabb` components.
+
+    run_system(world, create_skinned_aabbs)?;
+
+    let meshes = world
+        .run_system_once(
+            move |children: Query<&Children>, skinned: Query<(), With<SkinnedAabb>>| {
+                children
+                    .iter_descendants(player)
+                    .filter(|&entity| skinned.contains(entity))
+                    .collect::<Vec<_>>()
+            },
+        )
+        .or(Err(SkinnedAabbClipBakeError::FailedToRunSystem))?;
+
+    let mut bounds = vec![HashMap::new(); meshes.len()];
+
+    for (node_index, clip_id, duration) in clip_nodes {
+        let num_samples = ((duration * samples_per_second).ceil() as usize).max(1);
+        let mut clip_aabbs: Vec<Option<Aabb3d>> = vec![None; meshes.len()];
+
+        for sample_index in 0..=num_samples {
+            let time = duration * (sample_index as f32) / (num_samples as f32);
+
+            if let Some(mut animation_player) = world.get_mut::<AnimationPlayer>(player) {
+                animation_player
+                    .stop_all()
+                    .play(node_index)
+                    .set_seek_time(time);
+            }
+
+            // Evaluate the pose and update the joint transforms.
+
+            run_system(world, animate_targets)?;
+            run_system(world, mark_dirty_trees)?;
+            run_system(world, propagate_parent_transforms)?;
+            run_system(world, sync_simple_transforms)?;
+
+            let sample_aabbs = skinned_aabbs_in_player_space(world, player, &meshes);
+
+            for (clip_aabb, sample_aabb) in clip_aabbs.iter_mut().zip(sample_aabbs) {
+                *clip_aabb = match (*clip_aabb, sample_aabb) {
+                    (Some(l), Some(r)) => Some(l.merge(&r)),
+                    (l, r) => l.or(r),
+                };
+            }
+        }
+
+        for (mesh_bounds, clip_aabb) in bounds.iter_mut().zip(clip_aabbs) {
+            if let Some(clip_aabb) = clip_aabb {
+                mesh_bounds.insert(clip_id, PackedAabb3d::from(clip_aabb));
+            }
+        }
+    }
+
+    if let Some(mut animation_player) = world.get_mut::<AnimationPlayer>(player) {
+        *animation_player = saved_player;
+    }
+
+    for (mesh, clips) in meshes.into_iter().zip(bounds) {
+        world
+            .entity_mut(mesh)
+            .insert(SkinnedAabbClipBounds { player, clips });
+    }
+
+    Ok(())
+}
+
+// Update the `Aabb` of entities with `SkinnedAabbClipBounds` from the bounds of
+// the clips that are currently playing.
+//
+// Issues are reported through `SkinnedAabbDiagnostics`, the same as
+// `update_skinned_aabbs`.
+pub fn update_skinned_aabbs_from_clips(
+    mut query: Query<(
+        Entity,
+        &mut Aabb,
+        &SkinnedAabbClipBounds,
+        &GlobalTransform,
+        Option<&SkinnedAabbPadding>,
+    )>,
+    players: Query<(&AnimationPlayer, &AnimationGraphHandle, &GlobalTransform)>,
+    graphs: Res<Assets<AnimationGraph>>,
+    settings: Res<SkinnedAabbPluginSettings>,
+    mut diagnostics: ResMut<SkinnedAabbDiagnostics>,
+) {
+    let mut issues = Vec::new();
+
+    for (entity, mut entity_aabb, clip_bounds, world_from_entity, padding) in &mut query {
+        let Ok((player, graph_handle, world_from_player)) = players.get(clip_bounds.player) else {
+            continue;
+        };
+
+        let Some(graph) = graphs.get(&graph_handle.0) else {
+            continue;
+        };
+
+        let mut report = |issue: SkinnedAabbIssue| issues.push((entity, issue));
+
+        let mut player_aabb: Option<Aabb3d> = None;
+        let mut missing_clip = false;
+
+        for (&node_index, active_animation) in player.playing_animations() {
+            if active_animation.weight() <= 0.0 {
+                continue;
+            }
+
+            let Some(AnimationNodeType::Clip(clip)) = graph.get(node_index).map(|n| &n.node_type)
+            else {
+                continue;
+            };
+
+            let Some(&clip_aabb) = clip_bounds.clips.get(&clip.id()) else {
+                missing_clip = true;
+                break;
+            };
+
+            let clip_aabb = Aabb3d::from(clip_aabb);
+
+            player_aabb = Some(player_aabb.map_or(clip_aabb, |a| a.merge(&clip_aabb)));
+        }
+
+        // If a clip wasn't baked then we can't say anything useful, so leave
+        // the existing `Aabb` alone.
+        if missing_clip {
+            report(SkinnedAabbIssue::MissingClipBounds);
+            continue;
+        }
+
+        let Some(player_aabb) = player_aabb else {
+            continue;
+        };
+
+        let world_from_player = world_from_player.affine();
+
+        let Some(entity_from_world) =
+            entity_from_world(world_from_entity.affine()).filter(|_| world_from_player.is_finite())
+        else {
+            report(SkinnedAabbIssue::InvalidEntityTransform);
+            continue;
+        };
+
+        let entity_from_player = entity_from_world * world_from_player;
+        let updated = aabb_transformed_by(player_aabb.into(), entity_from_player);
+
+        let padding = entity_padding(&settings, padding);
+
+        let updated = updated.grow(Vec3A::splat(padding));
+
+        if !updated.min.is_finite() || !updated.max.is_finite() {
+            report(SkinnedAabbIssue::NonFiniteAabb);
+            continue;
+        }
+
+        *entity_aabb = Aabb::from_min_max(Vec3::from(updated.min), Vec3::from(updated.max));
+    }
+
+    // Issues of other entities are left to their own systems.
+    diagnostics.replace_issues(|entity| query.contains(entity), |_| true, issues);
+}
@@ -1,31 +1,49 @@
 use bevy_app::{App, Plugin, PostUpdate, Update};
 use bevy_asset::{Asset, AssetApp, AssetId, Assets, Handle};
-use bevy_camera::{primitives::Aabb, visibility::VisibilitySystems};
+use bevy_camera::{Camera, primitives::Aabb, visibility::VisibilitySystems};
 use bevy_ecs::{
+    batching::BatchingStrategy,
     change_detection::{Res, ResMut},
-    component::Component,
-    entity::Entity,
-    query::Without,
+    component::{Component, Mutable},
+    entity::{Entity, EntityHashMap, EntityHashSet},
+    hierarchy::ChildOf,
+    name::Name,
+    query::{Has, QueryItem, With, Without},
     resource::Resource,
-    schedule::IntoScheduleConfigs,
-    system::{Commands, Query},
+    schedule::{IntoScheduleConfigs, common_conditions::resource_exists},
+    system::{Commands, Local, Query, SystemParam},
     world::Mut,
 };
 #[cfg(feature = "trace")]
 use bevy_log::info_span;
+use bevy_log::warn;
 use bevy_math::{
-    Affine3A, Vec3, Vec3A,
+    Affine3A, Mat3A, Vec3, Vec3A,
     bounding::{Aabb3d, BoundingVolume},
 };
 use bevy_mesh::Mesh3d;
 use bevy_mesh::{
-    Mesh, VertexAttributeValues,
+    Mesh, MeshVertexAttribute, VertexAttributeValues,
     skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
 };
 use bevy_reflect::{Reflect, TypePath};
+use bevy_tasks::ComputeTaskPool;
 use bevy_transform::{TransformSystems, components::GlobalTransform};
+use bevy_utils::Parallel;
+use core::marker::PhantomData;
 
+pub mod broadphase;
+#[cfg(feature = "animation")]
+pub mod clip;
 pub mod debug;
+pub mod overlap;
+#[cfg(feature = "picking")]
+pub mod picking;
+pub mod raycast;
+pub mod root;
+pub mod screen;
+mod simplify;
+mod threshold;
 
 pub mod prelude {
     pub use crate::SkinnedAabbPlugin;
@@ -35,17 +53,109 @@ pub mod prelude {
 #[derive(Default)]
 pub struct SkinnedAabbPlugin;
 
+// Writes skinned bounds into a `SkinnedBoundsTarget` component other than
+// `Aabb`, like a physics engine's AABB. Requires `SkinnedAabbPlugin`, which can
+// be added before or after this plugin.
+//
+// Entities with `SkinnedAabbClipBounds` only get their `Aabb` updated from the
+// clip bounds, so their target is never updated.
+pub struct SkinnedBoundsPlugin<T: SkinnedBoundsTarget>(PhantomData<T>);
+
+impl<T: SkinnedBoundsTarget> Default for SkinnedBoundsPlugin<T> {
+    fn default() -> Self {
+        SkinnedBoundsPlugin(PhantomData)
+    }
+}
+
+impl<T: SkinnedBoundsTarget> Plugin for SkinnedBoundsPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            update_skinned_bounds::<T>
+                .after(TransformSystems::Propagate)
+                .before(overlap::send_hitbox_overlaps)
+                .before(broadphase::update_skinned_aabb_broadphase)
+                .before(root::update_skinned_aabb_roots),
+        );
+    }
+
+    // Check in `finish` rather than `build` so that the plugins can be added
+    // in any order.
+    fn finish(&self, app: &mut App) {
+        assert!(
+            app.is_plugin_added::<SkinnedAabbPlugin>(),
+            "SkinnedBoundsPlugin requires SkinnedAabbPlugin, which creates the skinned AABBs. Add it to the app.",
+        );
+    }
+}
+
+// A component that receives the bounds calculated by `update_skinned_bounds`.
+// Implemented for `Aabb`, which is used by `SkinnedAabbPlugin`.
+pub trait SkinnedBoundsTarget: Component<Mutability = Mutable> {
+    // If true, this target's update also writes `GlobalSkinnedAabb`,
+    // `PreviousSkinnedAabb` and `SweptSkinnedAabb` on entities that have an
+    // `Aabb`. Otherwise they're left to `Aabb`'s update, and this target's
+    // update only writes them on entities without an `Aabb`.
+    //
+    // Only one update should own them on each entity. Writing them twice
+    // wastes time, and the second write would replace the swept AABB with the
+    // current AABB. Defaults to false, and only `Aabb` sets it.
+    const WRITES_WORLD_BOUNDS_WITH_AABB: bool = false;
+
+    // Return the entity-space bounds that were last set, or None if they're
+    // not known. Used by `SkinnedAabbHysteresis`.
+    fn skinned_bounds(&self) -> Option<Aabb>;
+
+    // Set the entity-space bounds. The entity's transform is included for
+    // targets that are in world space.
+    fn set_skinned_bounds(&mut self, aabb: Aabb, world_from_entity: &GlobalTransform);
+}
+
+impl SkinnedBoundsTarget for Aabb {
+    const WRITES_WORLD_BOUNDS_WITH_AABB: bool = true;
+
+    fn skinned_bounds(&self) -> Option<Aabb> {
+        Some(*self)
+    }
+
+    fn set_skinned_bounds(&mut self, aabb: Aabb, _: &GlobalTransform) {
+        *self = aabb;
+    }
+}
+
 impl Plugin for SkinnedAabbPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<SkinnedAabbAsset>()
-            .insert_resource(SkinnedAabbPluginSettings { parallel: true })
+            .insert_resource(SkinnedAabbPluginSettings::default())
+            .init_resource::<SkinnedAabbInfluences>()
+            .init_resource::<SkinnedAabbJointPadding>()
+            .init_resource::<SkinnedAabbDiagnostics>()
+            .add_message::<overlap::SkinnedAabbHitboxOverlap>()
             .add_systems(Update, create_skinned_aabbs)
             .add_systems(
                 PostUpdate,
-                update_skinned_aabbs
-                    .after(TransformSystems::Propagate)
-                    .before(VisibilitySystems::CheckVisibility),
+                (
+                    update_skinned_aabbs
+                        .after(TransformSystems::Propagate)
+                        .before(VisibilitySystems::CheckVisibility),
+                    overlap::send_hitbox_overlaps.after(update_skinned_aabbs),
+                    broadphase::update_skinned_aabb_broadphase
+                        .after(update_skinned_aabbs)
+                        .run_if(resource_exists::<broadphase::SkinnedAabbBroadphase>),
+                    root::update_skinned_aabb_roots.after(update_skinned_aabbs),
+                ),
             );
+
+        #[cfg(feature = "animation")]
+        app.add_systems(
+            PostUpdate,
+            clip::update_skinned_aabbs_from_clips
+                .after(TransformSystems::Propagate)
+                .before(VisibilitySystems::CheckVisibility)
+                .before(overlap::send_hitbox_overlaps)
+                .before(broadphase::update_skinned_aabb_broadphase)
+                .before(root::update_skinned_aabb_roots),
+        );
     }
 }
 
@@ -53,21 +163,378 @@ impl Plugin for SkinnedAabbPlugin {
 pub struct SkinnedAabbPluginSettings {
     // If true, the skinned AABB update will run on multiple threads. Defaults to true.
     pub parallel: bool,
+
+    // How entities are split into batches when `parallel` is true. Defaults to
+    // `SkinnedAabbBatching::Default`.
+    pub batching: SkinnedAabbBatching,
+
+    // The number of batches assigned to each thread in the compute task pool.
+    // Increasing this can improve occupancy when the cost of each entity
+    // varies, at the price of more scheduling overhead. Only used by
+    // `SkinnedAabbBatching::Default`. Defaults to 1.
+    pub batches_per_thread: usize,
+
+    // If set, the `Aabb` is written with some slack and only rewritten when the
+    // skinned AABB escapes it or becomes much smaller. Defaults to none, which
+    // means the `Aabb` is written every update.
+    pub hysteresis: Option<SkinnedAabbHysteresis>,
+
+    // If set, new `SkinnedAabbAsset`s will merge small joint AABBs into their
+    // parents. Defaults to none.
+    pub simplification: Option<SkinnedAabbSimplification>,
+
+    // If set, new `SkinnedAabbAsset`s will include reduced levels of detail,
+    // and the update will choose a level based on the distance to the nearest
+    // camera. Defaults to none.
+    pub lod: Option<SkinnedAabbLodSettings>,
+
+    // How to handle meshes with vertices whose joint weights don't sum to one.
+    // Defaults to `SkinnedAabbWeightNormalization::Expand`.
+    pub weight_normalization: SkinnedAabbWeightNormalization,
+
+    // How to handle vertices whose joint weights are all zero. Defaults to
+    // `SkinnedAabbUnweightedVertices::Static`.
+    pub unweighted_vertices: SkinnedAabbUnweightedVertices,
+
+    // If true, new `SkinnedAabbAsset`s will only include vertices that are
+    // referenced by the mesh's indices. This is useful for meshes with unused
+    // vertices, or meshes that share a vertex buffer between several index
+    // ranges. Has no effect on meshes without indices. Defaults to false.
+    pub referenced_vertices_only: bool,
+
+    // If greater than zero, new `SkinnedAabbAsset`s will leave out influences
+    // with smaller weights, and grow the AABBs of the vertex's other joints to
+    // cover how far the dropped influences could move it. This has the same
+    // assumptions about joint translation and scale as
+    // `SkinnedAabbSimplification`. Influences that can't be bounded, like those
+    // from joints in a separate hierarchy, are kept. Defaults to zero.
+    pub min_joint_weight: f32,
+
+    // If true, AABBs are expanded by a margin that covers floating point
+    // rounding, so they're guaranteed to contain the skinned vertices instead
+    // of being within a small tolerance. The margin is a tiny fraction of the
+    // distance between the joints and the world origin. Defaults to false.
+    pub conservative_rounding: bool,
+
+    // If true, entities whose `SkinnedMesh::joints` don't match their inverse
+    // bindposes still get a `SkinnedAabb`, which only bounds the joints that
+    // resolve. Either way the entity is flagged with `SkinnedAabbDegraded`.
+    // Defaults to false, which means these entities get no `SkinnedAabb`.
+    pub allow_mismatched_joints: bool,
+
+    // Padding added to each side of the entity-space `Aabb`, for vertices
+    // that are moved by something other than skinning, like cloth or vertex
+    // shader effects. Can be overridden per entity with `SkinnedAabbPadding`,
+    // and joints can be padded with `SkinnedAabbJointPadding`. Defaults to
+    // zero.
+    pub padding: f32,
 }
 
 impl Default for SkinnedAabbPluginSettings {
     fn default() -> Self {
-        SkinnedAabbPluginSettings { parallel: true }
+        SkinnedAabbPluginSettings {
+            parallel: true,
+            batching: SkinnedAabbBatching::Default,
+            batches_per_thread: 1,
+            hysteresis: None,
+            simplification: None,
+            lod: None,
+            weight_normalization: SkinnedAabbWeightNormalization::Expand,
+            unweighted_vertices: SkinnedAabbUnweightedVertices::Static,
+            referenced_vertices_only: false,
+            min_joint_weight: 0.0,
+            conservative_rounding: false,
+            allow_mismatched_joints: false,
+            padding: 0.0,
+        }
+    }
+}
+
+// Settings for reducing how often the `Aabb` component is changed. This trades
+// some tightness for fewer component changes, which reduces the work done by
+// change detection and render extraction.
+//
+// Both values are relative to the largest half extent of the skinned AABB.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkinnedAabbHysteresis {
+    // Margin added to each side of the `Aabb` when it's written.
+    pub slack: f32,
+
+    // The `Aabb` is rewritten if any side is further than this from the skinned
+    // AABB. Should be greater than `slack`.
+    pub max_slack: f32,
+}
+
+impl Default for SkinnedAabbHysteresis {
+    fn default() -> Self {
+        SkinnedAabbHysteresis {
+            slack: 0.1,
+            max_slack: 0.3,
+        }
+    }
+}
+
+impl SkinnedAabbHysteresis {
+    // Given the currently stored `Aabb` and a freshly calculated skinned AABB,
+    // return the `Aabb` that should be stored, or None if the current one is
+    // still good enough.
+    pub fn update(&self, current: &Aabb, skinned: &Aabb) -> Option<Aabb> {
+        let size = skinned.half_extents.max_element();
+        let max_slack = Vec3A::splat(size * self.max_slack);
+
+        let (current_min, current_max) = (current.min(), current.max());
+        let (skinned_min, skinned_max) = (skinned.min(), skinned.max());
+
+        let escaped = skinned_min.cmplt(current_min).any() || skinned_max.cmpgt(current_max).any();
+
+        let too_loose = (skinned_min - current_min).cmpgt(max_slack).any()
+            || (current_max - skinned_max).cmpgt(max_slack).any();
+
+        if !escaped && !too_loose {
+            return None;
+        }
+
+        Some(Aabb {
+            center: skinned.center,
+            half_extents: skinned.half_extents + Vec3A::splat(size * self.slack),
+        })
+    }
+}
+
+// Settings for reducing the number of AABBs in a `SkinnedAabbAsset`. Each
+// merge moves a joint's AABB into its parent's AABB, so a chain of small joints
+// like fingers can be covered by a single AABB.
+//
+// The merged AABB accounts for any rotation of the merged joint, but assumes
+// that the joint's translation and scale relative to its parent don't change
+// from the bind pose. This is true for most skeletons, but skeletons that
+// animate translation or scale on merged joints may get incorrect AABBs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkinnedAabbSimplification {
+    // A joint is merged if its AABB fits within its parent's AABB grown by
+    // this fraction of the parent's largest half extent.
+    pub tolerance: f32,
+
+    // After merging joints that are within the tolerance, keep merging until
+    // there's at most this many AABBs or no more joints can be merged.
+    pub max_aabbs: usize,
+}
+
+impl Default for SkinnedAabbSimplification {
+    fn default() -> Self {
+        SkinnedAabbSimplification {
+            tolerance: 0.1,
+            max_aabbs: usize::MAX,
+        }
+    }
+}
+
+// Settings for distance based levels of detail. Each `SkinnedAabbAsset` gets
+// three levels: all joints, a reduced set of joints, and a single AABB attached
+// to the root joint.
+//
+// The reduced levels are created by `SkinnedAabbSimplification`, so they have
+// the same assumptions about joint translation and scale.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkinnedAabbLodSettings {
+    // The maximum number of AABBs in the reduced level.
+    pub reduced_max_aabbs: usize,
+
+    // Entities at least this far from the nearest camera use the reduced level.
+    pub reduced_distance: f32,
+
+    // Entities at least this far from the nearest camera use the single AABB
+    // level.
+    pub single_distance: f32,
+}
+
+impl Default for SkinnedAabbLodSettings {
+    fn default() -> Self {
+        SkinnedAabbLodSettings {
+            reduced_max_aabbs: 8,
+            reduced_distance: 20.0,
+            single_distance: 50.0,
+        }
     }
 }
 
+impl SkinnedAabbLodSettings {
+    // Return the level of detail for an entity that's `distance` from the
+    // nearest camera.
+    pub fn lod(&self, distance: f32) -> usize {
+        if distance >= self.single_distance {
+            2
+        } else if distance >= self.reduced_distance {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+// Linear blend skinning doesn't normalize joint weights, so if a vertex's
+// weights sum to `s` then the vertex is scaled by `s` towards the world origin.
+// These are the options for handling that.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SkinnedAabbWeightNormalization {
+    // Expand the AABB to account for the scaling. This is always conservative,
+    // but the AABB will be looser for meshes with unnormalized weights.
+    #[default]
+    Expand,
+
+    // Assume the weights are normalized. This is only correct if the mesh is
+    // rendered by a custom shader that normalizes the weights, or if the mesh
+    // has been fixed with `normalize_joint_weights`.
+    AssumeNormalized,
+}
+
+// Vertices with all zero joint weights aren't influenced by any joint, but
+// they're still rendered. These are the options for including them in the
+// skinned AABB.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SkinnedAabbUnweightedVertices {
+    // Bound the vertices with a static AABB in entity space, using their
+    // positions in the mesh.
+    #[default]
+    Static,
+
+    // Treat the vertices as if they were fully weighted to the given joint.
+    // The index is into `SkinnedMesh::joints`.
+    FallbackJoint(usize),
+
+    // Leave the vertices out of the skinned AABB. This may cause the mesh to
+    // be culled incorrectly.
+    Ignore,
+}
+
+// A pair of vertex attributes containing joint indices and weights, for meshes
+// that use more than `MAX_INFLUENCES` influences per vertex. The joint indices
+// must be `VertexFormat::Uint16x4` and the weights `VertexFormat::Float32x4`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkinnedAabbInfluenceAttributes {
+    pub joint_indices: MeshVertexAttribute,
+    pub joint_weights: MeshVertexAttribute,
+}
+
+impl SkinnedAabbInfluenceAttributes {
+    // The attributes used by Bevy's standard skinning.
+    pub const STANDARD: Self = SkinnedAabbInfluenceAttributes {
+        joint_indices: Mesh::ATTRIBUTE_JOINT_INDEX,
+        joint_weights: Mesh::ATTRIBUTE_JOINT_WEIGHT,
+    };
+}
+
+// The vertex attributes that new `SkinnedAabbAsset`s read joint indices and
+// weights from. Each pair adds `MAX_INFLUENCES` influences per vertex, and
+// pairs that are missing from a mesh are skipped. Defaults to the standard
+// `Mesh` attributes.
+#[derive(Resource, Clone, Debug)]
+pub struct SkinnedAabbInfluences(pub Vec<SkinnedAabbInfluenceAttributes>);
+
+impl Default for SkinnedAabbInfluences {
+    fn default() -> Self {
+        SkinnedAabbInfluences(vec![SkinnedAabbInfluenceAttributes::STANDARD])
+    }
+}
+
+// Strategies for splitting the parallel skinned AABB update into batches.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SkinnedAabbBatching {
+    // Let Bevy divide the entities evenly between threads.
+    #[default]
+    Default,
+
+    // Each batch contains a fixed number of entities.
+    FixedEntities(usize),
+
+    // Each batch contains roughly this many joints. Entities are grouped in
+    // query order until their joints add up to this, so an entity with a large
+    // skeleton can get a batch of its own while small skeletons share one. The
+    // grouping is redone every update, which costs a pass over the entities.
+    //
+    // This is only worth trying when skeleton sizes vary a lot and `Default`
+    // leaves threads idle. With the `systems` benchmark's uniform skeletons of
+    // 10 joints on a single core, `Joints(1024)` took 3.68ms for 100,000
+    // joints, against 2.98ms for `Default` and 2.88ms for the sequential
+    // update. Benchmark with your own scenes before switching.
+    Joints(usize),
+}
+
+impl SkinnedAabbPluginSettings {
+    // Return the `BatchingStrategy` for `par_iter_mut`. Not used by
+    // `SkinnedAabbBatching::Joints`, which spawns its own tasks.
+    fn batching_strategy(&self) -> BatchingStrategy {
+        match self.batching {
+            SkinnedAabbBatching::FixedEntities(batch_size) => {
+                BatchingStrategy::fixed(batch_size.max(1))
+            }
+            _ => BatchingStrategy::new().batches_per_thread(self.batches_per_thread.max(1)),
+        }
+    }
+}
+
+// Write the sizes of batches with roughly `joints_per_batch` joints to
+// `batch_sizes`, given the number of joints of each item in order. An item with
+// more joints than that gets a batch of its own.
+fn batch_sizes_by_joints(
+    joint_counts: impl IntoIterator<Item = usize>,
+    joints_per_batch: usize,
+    batch_sizes: &mut Vec<usize>,
+) {
+    batch_sizes.clear();
+
+    let mut batch_size = 0;
+    let mut batch_joints = 0;
+
+    for num_joints in joint_counts {
+        batch_size += 1;
+        batch_joints += num_joints;
+
+        if batch_joints >= joints_per_batch {
+            batch_sizes.push(batch_size);
+            batch_size = 0;
+            batch_joints = 0;
+        }
+    }
+
+    if batch_size > 0 {
+        batch_sizes.push(batch_size);
+    }
+}
+
+// Return an empty vector that keeps the allocation of `buffer`. Collecting in
+// place reuses the allocation when `T` and `U` have the same layout, such as
+// types that only differ by lifetimes.
+fn reuse_vec<T, U>(mut buffer: Vec<T>) -> Vec<U> {
+    buffer.clear();
+    buffer.into_iter().map(|_| unreachable!()).collect()
+}
+
 // Match the Mesh limits on joint indices (ATTRIBUTE_JOINT_INDEX = VertexFormat::Uint16x4)
 pub type JointIndex = u16;
 
+// The number of influences in each joint index and weight attribute.
+//
 // TODO: Bit janky hard-coding this here. Could petition for it to be added to
 // bevy_pbr alongside MAX_JOINTS?
 pub const MAX_INFLUENCES: usize = 4;
 
+// Relative margin used by `SkinnedAabbPluginSettings::conservative_rounding`.
+//
+// Each step of skinning a vertex and transforming its bounds is a short sum of
+// products. The rounding error of a sum of `n` products is at most
+// `n * u / (1 - n * u)` times the sum of their magnitudes, where `u` is half of
+// `f32::EPSILON`. This margin allows for well over a hundred such operations,
+// which covers our own calculations, the skinning itself, and the order of
+// operations differing between them.
+const ROUNDING_MARGIN: f32 = 64.0 * f32::EPSILON;
+
+// Joint weight sums within this distance of one are considered normalized.
+const WEIGHT_SUM_TOLERANCE: f32 = 1.0e-5;
+
+// Joint weight sums further than this distance from one will log a warning.
+const WEIGHT_SUM_WARNING_TOLERANCE: f32 = 0.01;
+
 // An `Aabb3d` without padding.
 #[derive(Copy, Clone, Debug, Reflect)]
 pub struct PackedAabb3d {
@@ -93,6 +560,112 @@ impl From<Aabb3d> for PackedAabb3d {
     }
 }
 
+// Problems found while updating a skinned AABB.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkinnedAabbIssue {
+    // The entity's `GlobalTransform` is non-finite or can't be inverted, so
+    // the `Aabb` was left unchanged. This happens if the entity is scaled to
+    // zero.
+    InvalidEntityTransform,
+
+    // Some joints had non-finite `GlobalTransform`s, so they were left out of
+    // the `Aabb`. The GPU can't render the vertices of these joints either.
+    InvalidJointTransform,
+
+    // The calculated AABB was non-finite, so the `Aabb` was left unchanged.
+    NonFiniteAabb,
+
+    // The entity's player is playing a clip that isn't in its
+    // `SkinnedAabbClipBounds`, so the `Aabb` was left unchanged. Bake the
+    // bounds again after adding clips to the graph.
+    MissingClipBounds,
+}
+
+// Issues found by the most recent `update_skinned_aabbs` and
+// `update_skinned_aabbs_from_clips`.
+#[derive(Resource, Default, Debug)]
+pub struct SkinnedAabbDiagnostics {
+    pub issues: Vec<(Entity, SkinnedAabbIssue)>,
+}
+
+impl SkinnedAabbDiagnostics {
+    // Replace the issues of entities where `updated` is true with `new_issues`,
+    // and drop the issues of other entities unless `keep` is true.
+    //
+    // Only warns about entities that didn't have issues in the previous
+    // update, so we don't spam the log every frame.
+    pub(crate) fn replace_issues(
+        &mut self,
+        updated: impl Fn(Entity) -> bool,
+        keep: impl Fn(Entity) -> bool,
+        new_issues: impl IntoIterator<Item = (Entity, SkinnedAabbIssue)>,
+    ) {
+        let mut previous = EntityHashSet::default();
+
+        self.issues.retain(|&(entity, _)| {
+            if updated(entity) {
+                previous.insert(entity);
+                return false;
+            }
+
+            keep(entity)
+        });
+
+        for (entity, issue) in new_issues {
+            if !previous.contains(&entity) {
+                warn!("Skinned AABB of entity {entity} could not be fully updated: {issue:?}.");
+            }
+
+            self.issues.push((entity, issue));
+        }
+    }
+}
+
+// Return the inverse of the entity's transform, or None if the transform is
+// non-finite or can't be inverted. The determinant check catches entities that
+// are scaled to zero, whose inverse can be finite but meaningless.
+pub(crate) fn entity_from_world(world_from_entity: Affine3A) -> Option<Affine3A> {
+    let entity_from_world = world_from_entity.inverse();
+
+    (world_from_entity.is_finite()
+        && entity_from_world.is_finite()
+        && (world_from_entity.matrix3.determinant().abs() > f32::MIN_POSITIVE))
+        .then_some(entity_from_world)
+}
+
+// Problems found while validating a skinned mesh before creating its
+// `SkinnedAabb`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkinnedAabbCreateError {
+    // The number of joints in `SkinnedMesh::joints` doesn't match the number
+    // of inverse bindposes.
+    MismatchedJointCount {
+        joints: usize,
+        inverse_bindposes: usize,
+    },
+}
+
+// Added to skinned mesh entities that failed validation. See
+// `SkinnedAabbPluginSettings::allow_mismatched_joints`. Removing this component
+// will make `create_skinned_aabbs` try again.
+#[derive(Component, Copy, Clone, Debug)]
+pub struct SkinnedAabbDegraded(pub SkinnedAabbCreateError);
+
+// Check that a skinned mesh is consistent with its inverse bindposes.
+pub fn validate_skinned_mesh(
+    skinned_mesh: &SkinnedMesh,
+    inverse_bindposes: &SkinnedMeshInverseBindposes,
+) -> Result<(), SkinnedAabbCreateError> {
+    if skinned_mesh.joints.len() != inverse_bindposes.len() {
+        return Err(SkinnedAabbCreateError::MismatchedJointCount {
+            joints: skinned_mesh.joints.len(),
+            inverse_bindposes: inverse_bindposes.len(),
+        });
+    }
+
+    Ok(())
+}
+
 // The assets that are used to create a `SkinnedAabbAsset`.
 #[derive(PartialEq, Eq, Debug)]
 pub struct SkinnedAabbSourceAssets {
@@ -111,9 +684,67 @@ pub struct SkinnedAabbAsset {
 
     // Mapping from `SkinnedAabbAsset::aabbs` index to `SkinnedMesh::joints` index.
     pub aabb_index_to_joint_index: Box<[JointIndex]>,
+
+    // Joints that were merged into an ancestor by simplification, in the order
+    // they were merged. Empty if simplification is disabled.
+    pub joint_merges: Box<[SkinnedAabbJointMerge]>,
+
+    // Reduced levels of detail, ordered from most to least detailed. Level 0 is
+    // `aabbs`, level 1 is `lods[0]`, and so on. Empty if levels of detail are
+    // disabled.
+    pub lods: Box<[SkinnedAabbLod]>,
+
+    // The minimum and maximum sum of each vertex's joint weights, or None if
+    // the weights are normalized.
+    pub joint_weight_sum_range: Option<(f32, f32)>,
+
+    // Entity-space AABB of vertices with no joint weights, or None if there
+    // are no such vertices or they're handled some other way.
+    pub unweighted_aabb: Option<PackedAabb3d>,
+}
+
+// A reduced level of detail for a `SkinnedAabbAsset`.
+#[derive(Debug)]
+pub struct SkinnedAabbLod {
+    // Joint-space AABB of each joint in this level.
+    pub aabbs: Box<[PackedAabb3d]>,
+
+    // Mapping from `SkinnedAabbLod::aabbs` index to `SkinnedMesh::joints` index.
+    pub aabb_index_to_joint_index: Box<[JointIndex]>,
+
+    // Joints that were merged into an ancestor when reducing the previous
+    // level to this one, in the order they were merged.
+    pub joint_merges: Box<[SkinnedAabbJointMerge]>,
+}
+
+// A joint whose AABB was merged into the AABB of one of its ancestors.
+#[derive(Copy, Clone, Debug)]
+pub struct SkinnedAabbJointMerge {
+    pub joint_index: JointIndex,
+    pub target_index: JointIndex,
+
+    // Scale from padding in the joint's space to padding in the target's space
+    // that contains it, for any rotation of the joint.
+    pub padding_scale: f32,
 }
 
 impl SkinnedAabbAsset {
+    pub fn num_lods(&self) -> usize {
+        1 + self.lods.len()
+    }
+
+    // Return the AABBs and joint indices of the given level of detail. Levels
+    // past the least detailed are clamped.
+    pub fn lod(&self, lod: usize) -> (&[PackedAabb3d], &[JointIndex]) {
+        match lod.min(self.lods.len()) {
+            0 => (&self.aabbs, &self.aabb_index_to_joint_index),
+            lod => (
+                &self.lods[lod - 1].aabbs,
+                &self.lods[lod - 1].aabb_index_to_joint_index,
+            ),
+        }
+    }
+
     pub fn aabb(&self, aabb_index: usize) -> PackedAabb3d {
         self.aabbs[aabb_index]
     }
@@ -129,17 +760,328 @@ impl SkinnedAabbAsset {
         joints: &Query<&GlobalTransform>,
     ) -> Option<Affine3A> {
         // TODO: Should return an error instead of silently failing?
-        let joint_index = *self.aabb_index_to_joint_index.get(aabb_index)? as usize;
-        let joint_entity = *skinned_mesh.joints.get(joint_index)?;
+        let joint_index = *self.aabb_index_to_joint_index.get(aabb_index)?;
 
-        Some(joints.get(joint_entity).ok()?.affine())
+        world_from_joint_index(joint_index, skinned_mesh, joints)
     }
 }
 
+fn world_from_joint_index(
+    joint_index: JointIndex,
+    skinned_mesh: &SkinnedMesh,
+    joints: &Query<&GlobalTransform>,
+) -> Option<Affine3A> {
+    let joint_entity = *skinned_mesh.joints.get(joint_index as usize)?;
+
+    Some(joints.get(joint_entity).ok()?.affine())
+}
+
 // TODO: Is this name misleading? Could be interpreted as the actual AABB.
 #[derive(Component, Debug, Default)]
 pub struct SkinnedAabb {
     pub asset: Handle<SkinnedAabbAsset>,
+
+    // Padding for each joint, indexed by level of detail and then by
+    // `SkinnedMesh::joints`. A joint that was merged into an ancestor passes
+    // its padding on to the ancestor. Empty if no joints are padded. See
+    // `SkinnedAabbPluginSettings::joint_padding`.
+    pub joint_padding: Box<[Box<[f32]>]>,
+}
+
+impl SkinnedAabb {
+    // Return the padding of each joint in the given level of detail. Levels
+    // past the least detailed are clamped.
+    pub fn lod_joint_padding(&self, lod: usize) -> &[f32] {
+        match self.joint_padding.len() {
+            0 => &[],
+            len => &self.joint_padding[lod.min(len - 1)],
+        }
+    }
+}
+
+// Return the joint-space `aabb` grown by the joint's padding, if any.
+fn padded_joint_aabb(
+    aabb: PackedAabb3d,
+    joint_padding: &[f32],
+    joint_index: JointIndex,
+) -> PackedAabb3d {
+    match joint_padding.get(joint_index as usize) {
+        Some(&padding) if padding != 0.0 => PackedAabb3d {
+            min: aabb.min - Vec3::splat(padding),
+            max: aabb.max + Vec3::splat(padding),
+        },
+        _ => aabb,
+    }
+}
+
+// The bound of a single joint. The joint-space AABB and the joint's transform
+// together make an oriented box in world space.
+//
+// Joint names aren't stored in `SkinnedAabbAsset`, since the asset is shared
+// by every entity with the same mesh and the joint entities can be named
+// differently. Names are resolved through `SkinnedMesh::joints` instead.
+#[derive(Copy, Clone, Debug)]
+pub struct SkinnedJointBound {
+    // Index into `SkinnedMesh::joints`.
+    pub joint_index: JointIndex,
+
+    pub joint_entity: Entity,
+
+    // Joint-space AABB, including any joint padding.
+    pub aabb: PackedAabb3d,
+
+    pub world_from_joint: Affine3A,
+}
+
+impl SkinnedJointBound {
+    // Return the world-space AABB of the oriented box.
+    pub fn world_aabb(&self) -> Aabb3d {
+        aabb_transformed_by(self.aabb, self.world_from_joint)
+    }
+}
+
+// Return the bound of each joint with an AABB in the most detailed level.
+// Joints with missing or non-finite transforms are skipped.
+//
+// If the mesh's joint weights are unnormalized then the joint bounds don't
+// contain the vertices, although their union is still used for the entity's
+// `Aabb`.
+pub fn skinned_joint_bounds<'a>(
+    skinned_aabb: &'a SkinnedAabb,
+    asset: &'a SkinnedAabbAsset,
+    skinned_mesh: &'a SkinnedMesh,
+    joints: &'a Query<&GlobalTransform>,
+) -> impl Iterator<Item = SkinnedJointBound> + 'a {
+    asset
+        .aabbs
+        .iter()
+        .zip(&asset.aabb_index_to_joint_index)
+        .filter_map(move |(&aabb, &joint_index)| {
+            let joint_entity = *skinned_mesh.joints.get(joint_index as usize)?;
+            let world_from_joint = joints.get(joint_entity).ok()?.affine();
+
+            if !world_from_joint.is_finite() {
+                return None;
+            }
+
+            Some(SkinnedJointBound {
+                joint_index,
+                joint_entity,
+                aabb: padded_joint_aabb(aabb, skinned_aabb.lod_joint_padding(0), joint_index),
+                world_from_joint,
+            })
+        })
+}
+
+// Query for the bounds of individual joints of entities with `SkinnedAabb`.
+#[derive(SystemParam)]
+pub struct SkinnedJointBounds<'w, 's> {
+    pub query: Query<'w, 's, (&'static SkinnedAabb, &'static SkinnedMesh)>,
+    pub joints: Query<'w, 's, &'static GlobalTransform>,
+    pub names: Query<'w, 's, &'static Name>,
+    pub assets: Res<'w, Assets<SkinnedAabbAsset>>,
+}
+
+impl SkinnedJointBounds<'_, '_> {
+    // Return the bound of each joint of `entity`. See `skinned_joint_bounds`.
+    pub fn iter(&self, entity: Entity) -> impl Iterator<Item = SkinnedJointBound> + '_ {
+        let found = self
+            .query
+            .get(entity)
+            .ok()
+            .and_then(|(skinned_aabb, skinned_mesh)| {
+                Some((
+                    skinned_aabb,
+                    self.assets.get(&skinned_aabb.asset)?,
+                    skinned_mesh,
+                ))
+            });
+
+        found
+            .into_iter()
+            .flat_map(|(skinned_aabb, asset, skinned_mesh)| {
+                skinned_joint_bounds(skinned_aabb, asset, skinned_mesh, &self.joints)
+            })
+    }
+
+    // Return the bound of the first joint of `entity` with the given `Name`.
+    // Returns None if there's no such joint, or if the joint has no AABB of
+    // its own, for example because it has no vertices or was merged into its
+    // parent by simplification.
+    pub fn named(&self, entity: Entity, name: &str) -> Option<SkinnedJointBound> {
+        let (_, skinned_mesh) = self.query.get(entity).ok()?;
+
+        let joint_index = skinned_mesh.joints.iter().position(|&joint_entity| {
+            self.names
+                .get(joint_entity)
+                .is_ok_and(|joint_name| joint_name.as_str() == name)
+        })?;
+
+        self.iter(entity)
+            .find(|bound| bound.joint_index as usize == joint_index)
+    }
+}
+
+// World-space AABB of the skinned mesh. `update_skinned_aabbs` fills this in
+// for entities that have it, directly from the joints. This is tighter than
+// transforming the entity-space `Aabb` to world space.
+//
+// Entities that are updated from `SkinnedAabbClipBounds` are not supported.
+// The AABB is None if it couldn't be calculated. If the entity also has a
+// `SkinnedBoundsTarget` other than `Aabb`, this is only updated by
+// `update_skinned_aabbs`.
+#[derive(Component, Copy, Clone, Debug, Default)]
+pub struct GlobalSkinnedAabb(pub Option<Aabb3d>);
+
+// World-space AABB of the skinned mesh from the previous update of
+// `update_skinned_aabbs`. Used to calculate `SweptSkinnedAabb`.
+#[derive(Component, Copy, Clone, Debug, Default)]
+pub struct PreviousSkinnedAabb(pub Option<Aabb3d>);
+
+// World-space AABB of the volume the skinned mesh swept over since the previous
+// update, for motion blur, disocclusion and continuous collision.
+// `update_skinned_aabbs` fills this in for entities that have it.
+//
+// This is the union of each joint's box at its previous and current transform,
+// which is the same as the union of the previous and current joint-based world
+// AABBs. That's tighter than the union of the transformed entity-space `Aabb`s.
+// The motion in between isn't included, so a joint that rotates quickly can
+// briefly leave the bounds.
+//
+// On the first update, or if the previous AABB couldn't be calculated, this is
+// the current AABB. Entities that are updated from `SkinnedAabbClipBounds` are
+// not supported. Like `GlobalSkinnedAabb`, this is only updated by
+// `update_skinned_aabbs` if the entity has an `Aabb`.
+#[derive(Component, Copy, Clone, Debug, Default)]
+#[require(PreviousSkinnedAabb)]
+pub struct SweptSkinnedAabb(pub Option<Aabb3d>);
+
+// Return the world-space bounds of an entity from its `GlobalSkinnedAabb` if
+// it has one, or else from its `Aabb`.
+pub(crate) fn entity_world_aabb(
+    aabb: Option<&Aabb>,
+    global_aabb: Option<&GlobalSkinnedAabb>,
+    world_from_entity: &GlobalTransform,
+) -> Option<Aabb3d> {
+    if let Some(world_aabb) = global_aabb.and_then(|global_aabb| global_aabb.0) {
+        return Some(world_aabb);
+    }
+
+    let aabb = aabb?;
+
+    Some(aabb_transformed_by(
+        PackedAabb3d {
+            min: aabb.min().into(),
+            max: aabb.max().into(),
+        },
+        world_from_entity.affine(),
+    ))
+}
+
+// Overrides `SkinnedAabbPluginSettings::padding` for this entity.
+#[derive(Component, Copy, Clone, Debug, Default)]
+pub struct SkinnedAabbPadding(pub f32);
+
+// Return the padding of an entity with an optional `SkinnedAabbPadding`.
+// Negative padding is treated as zero.
+pub(crate) fn entity_padding(
+    settings: &SkinnedAabbPluginSettings,
+    padding: Option<&SkinnedAabbPadding>,
+) -> f32 {
+    padding
+        .map_or(settings.padding, |padding| padding.0)
+        .max(0.0)
+}
+
+// Padding added to the AABBs of joints whose `Name` matches a pattern.
+// Patterns can contain `*` wildcards, and the first match is used. The padding
+// is in joint space, so it's scaled along with the joint. Joints merged by
+// simplification or levels of detail pass their padding on to the joint
+// they're merged into. Applies to `SkinnedAabb` components created after it's
+// set. Defaults to none.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SkinnedAabbJointPadding(pub Vec<(String, f32)>);
+
+// Return true if `name` matches `pattern`, where `*` in the pattern matches any
+// sequence of characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+
+            // Try every possible length for the wildcard.
+            name.char_indices()
+                .map(|(index, _)| index)
+                .chain([name.len()])
+                .any(|index| matches_pattern(rest, &name[index..]))
+        }
+    }
+}
+
+// Return the padding of each joint from the rules in `joint_padding`, or an
+// empty slice if no joints are padded.
+fn joint_padding(
+    skinned_mesh: &SkinnedMesh,
+    names: &Query<&Name>,
+    joint_padding: &[(String, f32)],
+) -> Box<[f32]> {
+    if joint_padding.is_empty() {
+        return Box::new([]);
+    }
+
+    let padding = skinned_mesh
+        .joints
+        .iter()
+        .map(|&joint_entity| {
+            let Ok(name) = names.get(joint_entity) else {
+                return 0.0;
+            };
+
+            joint_padding
+                .iter()
+                .find(|(pattern, _)| matches_pattern(pattern, name.as_str()))
+                .map_or(0.0, |&(_, padding)| padding)
+        })
+        .collect::<Box<[f32]>>();
+
+    match padding.iter().any(|&padding| padding != 0.0) {
+        true => padding,
+        false => Box::new([]),
+    }
+}
+
+// Return the padding of each joint in each level of detail of `asset`, given
+// the padding of each joint before simplification. Merged joints pass their
+// padding on to the joint they were merged into, scaled so that it covers any
+// rotation of the merged joint.
+fn lod_joint_padding(asset: &SkinnedAabbAsset, joint_padding: Box<[f32]>) -> Box<[Box<[f32]>]> {
+    if joint_padding.is_empty() {
+        return Box::new([]);
+    }
+
+    let mut padding = joint_padding;
+
+    [&asset.joint_merges]
+        .into_iter()
+        .chain(asset.lods.iter().map(|lod| &lod.joint_merges))
+        .map(|joint_merges| {
+            for merge in joint_merges {
+                let (joint_index, target_index) =
+                    (merge.joint_index as usize, merge.target_index as usize);
+
+                if let (Some(&joint), Some(&target)) =
+                    (padding.get(joint_index), padding.get(target_index))
+                {
+                    padding[target_index] = target.max(joint * merge.padding_scale);
+                }
+            }
+
+            padding.clone()
+        })
+        .collect()
 }
 
 // Return `aabb` extended to include `point`. If `aabb` is none, return the
@@ -162,42 +1104,102 @@ struct Influence {
     joint_index: usize,
 }
 
+// Joint indices and weights of one `SkinnedAabbInfluenceAttributes` pair.
+type InfluenceSet<'a> = (&'a [[u16; 4]], &'a [[f32; 4]]);
+
+// The vertex attributes of a mesh that are used for skinning.
+#[derive(Default, Clone)]
+struct SkinningAttributes<'a> {
+    positions: &'a [[f32; 3]],
+    influence_sets: Vec<InfluenceSet<'a>>,
+}
+
+impl<'a> SkinningAttributes<'a> {
+    // Returns None if the mesh has no positions or none of the influence
+    // attributes, or if the attributes have mismatched lengths. Influence
+    // attributes that are missing from the mesh are skipped.
+    fn new(mesh: &'a Mesh, attributes: &[SkinnedAabbInfluenceAttributes]) -> Option<Self> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+
+        let influence_sets = attributes
+            .iter()
+            .filter_map(|attributes| {
+                match (
+                    mesh.attribute(attributes.joint_indices),
+                    mesh.attribute(attributes.joint_weights),
+                ) {
+                    (
+                        Some(VertexAttributeValues::Uint16x4(joint_indices)),
+                        Some(VertexAttributeValues::Float32x4(joint_weights)),
+                    ) => Some((joint_indices.as_slice(), joint_weights.as_slice())),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // TODO: Should be an error?
+        if influence_sets.is_empty()
+            || influence_sets.iter().any(|(joint_indices, joint_weights)| {
+                (joint_indices.len() != positions.len()) | (joint_weights.len() != positions.len())
+            })
+        {
+            return None;
+        }
+
+        Some(SkinningAttributes {
+            positions,
+            influence_sets,
+        })
+    }
+
+    fn num_influences(&self) -> usize {
+        self.influence_sets.len() * MAX_INFLUENCES
+    }
+
+    fn influence(&self, vertex_index: usize, influence_index: usize) -> (u16, f32) {
+        let (joint_indices, joint_weights) = self.influence_sets[influence_index / MAX_INFLUENCES];
+        let component = influence_index % MAX_INFLUENCES;
+
+        (
+            joint_indices[vertex_index][component],
+            joint_weights[vertex_index][component],
+        )
+    }
+
+    fn weights(&self, vertex_index: usize) -> impl Iterator<Item = f32> + '_ {
+        self.influence_sets
+            .iter()
+            .flat_map(move |(_, joint_weights)| joint_weights[vertex_index])
+    }
+}
+
 /// Iterator over all vertex influences with non-zero weight.
 #[derive(Default)]
 struct InfluenceIterator<'a> {
     vertex_index: usize,
     influence_index: usize,
-    positions: &'a [[f32; 3]],
-    joint_indices: &'a [[u16; 4]],
-    joint_weights: &'a [[f32; 4]],
+    attributes: SkinningAttributes<'a>,
+    referenced: Option<&'a [bool]>,
 }
 
 impl<'a> InfluenceIterator<'a> {
-    fn new(mesh: &'a Mesh) -> Self {
-        if let (
-            Some(VertexAttributeValues::Float32x3(positions)),
-            Some(VertexAttributeValues::Uint16x4(joint_indices)),
-            Some(VertexAttributeValues::Float32x4(joint_weights)),
-        ) = (
-            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
-            mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX),
-            mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT),
-        ) {
-            if (joint_indices.len() != positions.len()) | (joint_weights.len() != positions.len()) {
-                // TODO: Should be an error?
-                return InfluenceIterator::default();
-            }
+    // If `referenced` is set then only vertices marked as referenced are
+    // included.
+    fn new(attributes: Option<SkinningAttributes<'a>>, referenced: Option<&'a [bool]>) -> Self {
+        let Some(attributes) = attributes else {
+            return InfluenceIterator::default();
+        };
 
-            return InfluenceIterator {
-                vertex_index: 0,
-                influence_index: 0,
-                positions,
-                joint_indices,
-                joint_weights,
-            };
+        InfluenceIterator {
+            vertex_index: 0,
+            influence_index: 0,
+            attributes,
+            referenced,
         }
-
-        InfluenceIterator::default()
     }
 }
 
@@ -205,22 +1207,30 @@ impl Iterator for InfluenceIterator<'_> {
     type Item = Influence;
 
     fn next(&mut self) -> Option<Influence> {
+        let num_influences = self.attributes.num_influences();
+
         loop {
-            assert!(self.influence_index <= MAX_INFLUENCES);
-            assert!(self.vertex_index <= self.positions.len());
+            assert!(self.influence_index <= num_influences);
+            assert!(self.vertex_index <= self.attributes.positions.len());
 
-            if self.influence_index >= MAX_INFLUENCES {
+            if self.influence_index >= num_influences {
                 self.influence_index = 0;
                 self.vertex_index += 1;
             }
 
-            if self.vertex_index >= self.positions.len() {
+            if self.vertex_index >= self.attributes.positions.len() {
                 break None;
             }
 
-            let position = Vec3::from_array(self.positions[self.vertex_index]);
-            let joint_index = self.joint_indices[self.vertex_index][self.influence_index];
-            let joint_weight = self.joint_weights[self.vertex_index][self.influence_index];
+            if (self.influence_index == 0) && !is_referenced(self.referenced, self.vertex_index) {
+                self.vertex_index += 1;
+                continue;
+            }
+
+            let position = Vec3::from_array(self.attributes.positions[self.vertex_index]);
+            let (joint_index, joint_weight) = self
+                .attributes
+                .influence(self.vertex_index, self.influence_index);
 
             self.influence_index += 1;
 
@@ -234,45 +1244,365 @@ impl Iterator for InfluenceIterator<'_> {
     }
 }
 
+// Iterator over the positions of vertices whose joint weights are all zero.
+#[derive(Clone)]
+struct UnweightedPositions<'a> {
+    vertex_index: usize,
+    attributes: SkinningAttributes<'a>,
+    referenced: Option<&'a [bool]>,
+}
+
+impl<'a> UnweightedPositions<'a> {
+    // Returns None if there are no unweighted vertices.
+    fn new(attributes: SkinningAttributes<'a>, referenced: Option<&'a [bool]>) -> Option<Self> {
+        let iterator = UnweightedPositions {
+            vertex_index: 0,
+            attributes,
+            referenced,
+        };
+
+        iterator.clone().next()?;
+
+        Some(iterator)
+    }
+}
+
+impl Iterator for UnweightedPositions<'_> {
+    type Item = Vec3;
+
+    fn next(&mut self) -> Option<Vec3> {
+        loop {
+            let vertex_index = self.vertex_index;
+            let position = self.attributes.positions.get(vertex_index)?;
+
+            self.vertex_index += 1;
+
+            if is_referenced(self.referenced, vertex_index)
+                && self.attributes.weights(vertex_index).all(|w| w <= 0.0)
+            {
+                break Some(Vec3::from_array(*position));
+            }
+        }
+    }
+}
+
+// Return which vertices are referenced by the mesh's indices, or None if the
+// mesh has no indices.
+fn referenced_vertices(mesh: &Mesh) -> Option<Box<[bool]>> {
+    let indices = mesh.indices()?;
+    let mut referenced = vec![false; mesh.count_vertices()].into_boxed_slice();
+
+    for index in indices.iter() {
+        if let Some(referenced) = referenced.get_mut(index) {
+            *referenced = true;
+        }
+    }
+
+    Some(referenced)
+}
+
+// If `referenced` is none then all vertices are considered referenced.
+fn is_referenced(referenced: Option<&[bool]>, vertex_index: usize) -> bool {
+    referenced.is_none_or(|referenced| referenced.get(vertex_index).copied().unwrap_or(false))
+}
+
 fn create_skinned_aabb_asset(
     mesh: &Mesh,
     mesh_handle: AssetId<Mesh>,
     inverse_bindposes: &SkinnedMeshInverseBindposes,
     inverse_bindposes_handle: AssetId<SkinnedMeshInverseBindposes>,
+    settings: &SkinnedAabbPluginSettings,
+    influence_attributes: &[SkinnedAabbInfluenceAttributes],
+    joint_parents: Option<&[Option<usize>]>,
 ) -> SkinnedAabbAsset {
     let num_joints = inverse_bindposes.len();
 
     // TODO: Error if num_joints exceeds JointIndex limits?
 
+    // If the skinned mesh has a different number of joints then make sure the
+    // hierarchy only refers to joints with inverse bindposes.
+
+    let joint_parents = joint_parents.map(|joint_parents| {
+        (0..num_joints)
+            .map(|joint_index| {
+                joint_parents
+                    .get(joint_index)
+                    .copied()
+                    .flatten()
+                    .filter(|&parent| parent < num_joints)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let joint_parents = joint_parents.as_deref();
+
     // Allocate an optional AABB for each joint.
 
     let mut optional_aabbs: Box<[Option<Aabb3d>]> = vec![None; num_joints].into_boxed_slice();
 
-    // Iterate over all influences and add the vertex position to the joint's AABB.
+    let referenced = settings
+        .referenced_vertices_only
+        .then(|| referenced_vertices(mesh))
+        .flatten();
+
+    let referenced = referenced.as_deref();
+
+    let attributes = SkinningAttributes::new(mesh, influence_attributes);
+
+    // Iterate over all influences and add the vertex position to the joint's
+    // AABB. If there's a weight threshold then we need the joint hierarchy to
+    // bound the error.
+
+    if (settings.min_joint_weight > 0.0)
+        && let Some(attributes) = &attributes
+        && let Some(joint_parents) = joint_parents
+    {
+        threshold::add_thresholded_influences(
+            &mut optional_aabbs,
+            attributes,
+            referenced,
+            inverse_bindposes,
+            joint_parents,
+            settings.min_joint_weight,
+        );
+    } else {
+        for Influence {
+            position,
+            joint_index,
+        } in InfluenceIterator::new(attributes.clone(), referenced)
+        {
+            // TODO: Replace assert with error?
+
+            assert!(
+                joint_index < num_joints,
+                "Joint index out of range. Joint index = {joint_index}, number of joints = {num_joints}.",
+            );
+
+            let jointspace_position = inverse_bindposes[joint_index].transform_point3(position);
+
+            optional_aabbs[joint_index] = Some(merge(
+                optional_aabbs[joint_index],
+                Vec3A::from(jointspace_position),
+            ));
+        }
+    }
+
+    // Handle vertices that weren't included by `InfluenceIterator` because they
+    // have no weights.
 
-    for Influence {
-        position,
-        joint_index,
-    } in InfluenceIterator::new(mesh)
+    let mut unweighted_aabb = None;
+
+    if let Some(unweighted_positions) = attributes
+        .clone()
+        .and_then(|attributes| UnweightedPositions::new(attributes, referenced))
     {
-        // TODO: Replace assert with error?
+        match settings.unweighted_vertices {
+            SkinnedAabbUnweightedVertices::Static => {
+                unweighted_aabb = unweighted_positions
+                    .fold(None, |aabb, position| {
+                        Some(merge(aabb, Vec3A::from(position)))
+                    })
+                    .map(PackedAabb3d::from);
+            }
+            SkinnedAabbUnweightedVertices::FallbackJoint(joint_index) => {
+                if joint_index < num_joints {
+                    for position in unweighted_positions {
+                        let jointspace_position =
+                            inverse_bindposes[joint_index].transform_point3(position);
+
+                        optional_aabbs[joint_index] = Some(merge(
+                            optional_aabbs[joint_index],
+                            Vec3A::from(jointspace_position),
+                        ));
+                    }
+                } else {
+                    warn!(
+                        "Mesh {mesh_handle:?} has vertices with no joint weights, but the fallback joint index is out of range. Joint index = {joint_index}, number of joints = {num_joints}.",
+                    );
+                }
+            }
+            SkinnedAabbUnweightedVertices::Ignore => (),
+        }
+    }
 
-        assert!(
-            joint_index < num_joints,
-            "Joint index out of range. Joint index = {joint_index}, number of joints = {num_joints}.",
+    // Round the joint-space AABBs outwards. The magnitude of the error depends
+    // on the vertex positions, so use the largest position in the mesh.
+
+    if settings.conservative_rounding
+        && let Some(attributes) = &attributes
+    {
+        let max_position = attributes
+            .positions
+            .iter()
+            .fold(Vec3A::ZERO, |max, &position| {
+                max.max(Vec3A::from_array(position).abs())
+            });
+
+        for (joint_index, optional_aabb) in optional_aabbs.iter_mut().enumerate() {
+            if let Some(aabb) = optional_aabb {
+                let joint_from_model = Affine3A::from_mat4(inverse_bindposes[joint_index]);
+
+                *aabb =
+                    rounded_outward(*aabb, rounding_magnitude(max_position, &[joint_from_model]));
+            }
+        }
+
+        unweighted_aabb = unweighted_aabb.map(|aabb: PackedAabb3d| {
+            let aabb = Aabb3d::from(aabb);
+
+            PackedAabb3d::from(rounded_outward(aabb, extent(aabb)))
+        });
+    }
+
+    let mut joint_merges = Vec::new();
+
+    if let (Some(simplification), Some(joint_parents)) = (settings.simplification, joint_parents) {
+        joint_merges = simplify::simplify_joint_aabbs(
+            &mut optional_aabbs,
+            joint_parents,
+            inverse_bindposes,
+            &simplification,
+        );
+    }
+
+    let (aabbs, aabb_index_to_joint_index) = pack_joint_aabbs(&optional_aabbs);
+
+    // Check for unnormalized weights. Exporters and vertex formats often lose a
+    // bit of precision, so only warn if the error is significant. But always
+    // expand the AABB if requested, since small errors still matter for
+    // meshes far from the origin.
+
+    let joint_weight_sum_range = attributes
+        .as_ref()
+        .and_then(|attributes| joint_weight_sum_range(attributes, referenced))
+        .filter(|&(min, max)| {
+            (min < (1.0 - WEIGHT_SUM_TOLERANCE)) || (max > (1.0 + WEIGHT_SUM_TOLERANCE))
+        });
+
+    if let Some((min, max)) = joint_weight_sum_range
+        && ((min < (1.0 - WEIGHT_SUM_WARNING_TOLERANCE))
+            || (max > (1.0 + WEIGHT_SUM_WARNING_TOLERANCE)))
+    {
+        warn!(
+            "Mesh {mesh_handle:?} has joint weights that don't sum to one (min = {min}, max = {max}). {}",
+            match settings.weight_normalization {
+                SkinnedAabbWeightNormalization::Expand =>
+                    "The skinned AABB will be expanded to account for this.",
+                SkinnedAabbWeightNormalization::AssumeNormalized =>
+                    "The skinned AABB may be incorrect unless the weights are normalized when rendering.",
+            }
         );
+    }
+
+    let joint_weight_sum_range = match settings.weight_normalization {
+        SkinnedAabbWeightNormalization::Expand => joint_weight_sum_range,
+        SkinnedAabbWeightNormalization::AssumeNormalized => None,
+    };
+
+    // Create the reduced levels of detail by simplifying the previous level.
+
+    let mut lods = Vec::<SkinnedAabbLod>::new();
+
+    if let (Some(lod), Some(joint_parents)) = (settings.lod, joint_parents) {
+        for max_aabbs in [lod.reduced_max_aabbs, 1] {
+            let joint_merges = simplify::simplify_joint_aabbs(
+                &mut optional_aabbs,
+                joint_parents,
+                inverse_bindposes,
+                &SkinnedAabbSimplification {
+                    tolerance: 0.0,
+                    max_aabbs,
+                },
+            );
+
+            let (aabbs, aabb_index_to_joint_index) = pack_joint_aabbs(&optional_aabbs);
+
+            lods.push(SkinnedAabbLod {
+                aabbs,
+                aabb_index_to_joint_index,
+                joint_merges: joint_merges.into(),
+            });
+        }
+    }
+
+    SkinnedAabbAsset {
+        source: SkinnedAabbSourceAssets {
+            mesh: mesh_handle,
+            inverse_bindposes: inverse_bindposes_handle,
+        },
+        aabbs,
+        aabb_index_to_joint_index,
+        joint_merges: joint_merges.into(),
+        lods: lods.into(),
+        joint_weight_sum_range,
+        unweighted_aabb,
+    }
+}
 
-        let jointspace_position = inverse_bindposes[joint_index].transform_point3(position);
+// Return the minimum and maximum sum of each vertex's joint weights. Vertices
+// with no weights or that aren't referenced are ignored.
+fn joint_weight_sum_range(
+    attributes: &SkinningAttributes,
+    referenced: Option<&[bool]>,
+) -> Option<(f32, f32)> {
+    (0..attributes.positions.len())
+        .filter(|&vertex_index| is_referenced(referenced, vertex_index))
+        .map(|vertex_index| {
+            attributes
+                .weights(vertex_index)
+                .filter(|&w| w > 0.0)
+                .sum::<f32>()
+        })
+        .filter(|&sum| sum > 0.0)
+        .fold(None, |range, sum| match range {
+            Some((min, max)) => Some((sum.min(min), sum.max(max))),
+            None => Some((sum, sum)),
+        })
+}
 
-        optional_aabbs[joint_index] = Some(merge(
-            optional_aabbs[joint_index],
-            Vec3A::from(jointspace_position),
-        ));
+// Scale each vertex's joint weights so that they sum to one. Vertices with no
+// weights are left unchanged. Returns true if any weights were changed.
+//
+// `attributes` should match `SkinnedAabbInfluences`.
+pub fn normalize_joint_weights(
+    mesh: &mut Mesh,
+    attributes: &[SkinnedAabbInfluenceAttributes],
+) -> bool {
+    let Some(sums) = SkinningAttributes::new(mesh, attributes).map(|skinning_attributes| {
+        (0..skinning_attributes.positions.len())
+            .map(|vertex_index| {
+                skinning_attributes
+                    .weights(vertex_index)
+                    .filter(|&w| w > 0.0)
+                    .sum::<f32>()
+            })
+            .collect::<Vec<_>>()
+    }) else {
+        return false;
+    };
+
+    if sums.iter().all(|&sum| (sum <= 0.0) || (sum == 1.0)) {
+        return false;
+    }
+
+    for attributes in attributes {
+        if let Some(VertexAttributeValues::Float32x4(joint_weights)) =
+            mesh.attribute_mut(attributes.joint_weights)
+        {
+            for (weights, &sum) in joint_weights.iter_mut().zip(&sums) {
+                if (sum > 0.0) && (sum != 1.0) {
+                    *weights = weights.map(|w| w.max(0.0) / sum);
+                }
+            }
+        }
     }
 
-    // Create the final list of AABBs. This will only contain joints that had
-    // vertices skinned to them.
+    true
+}
 
+// Create the final list of AABBs. This will only contain joints that had
+// vertices skinned to them.
+fn pack_joint_aabbs(optional_aabbs: &[Option<Aabb3d>]) -> (Box<[PackedAabb3d]>, Box<[JointIndex]>) {
     let num_aabbs = optional_aabbs.iter().filter(|o| o.is_some()).count();
 
     let mut aabbs = Vec::<PackedAabb3d>::with_capacity(num_aabbs);
@@ -288,14 +1618,7 @@ fn create_skinned_aabb_asset(
     assert!(aabbs.len() == num_aabbs);
     assert!(aabb_index_to_joint_index.len() == num_aabbs);
 
-    SkinnedAabbAsset {
-        source: SkinnedAabbSourceAssets {
-            mesh: mesh_handle,
-            inverse_bindposes: inverse_bindposes_handle,
-        },
-        aabbs: aabbs.into(),
-        aabb_index_to_joint_index: aabb_index_to_joint_index.into(),
-    }
+    (aabbs.into(), aabb_index_to_joint_index.into())
 }
 
 #[cfg(feature = "trace")]
@@ -303,12 +1626,16 @@ fn asset_handle_to_string<A: Asset>(h: &Handle<A>) -> &str {
     h.path().and_then(|p| p.path().to_str()).unwrap_or("")
 }
 
+#[allow(clippy::too_many_arguments)]
 fn create_skinned_aabb_component(
     skinned_aabb_assets: &mut ResMut<Assets<SkinnedAabbAsset>>,
     mesh_assets: &Assets<Mesh>,
     mesh_handle: &Handle<Mesh>,
     inverse_bindposes_assets: &Assets<SkinnedMeshInverseBindposes>,
     inverse_bindposes_handle: &Handle<SkinnedMeshInverseBindposes>,
+    settings: &SkinnedAabbPluginSettings,
+    influence_attributes: &[SkinnedAabbInfluenceAttributes],
+    joint_parents: impl FnOnce() -> Vec<Option<usize>>,
 ) -> Option<SkinnedAabb> {
     // If the source assets are invalid then return None.
     //
@@ -346,6 +1673,7 @@ fn create_skinned_aabb_component(
     {
         return Some(SkinnedAabb {
             asset: existing_asset_handle,
+            ..Default::default()
         });
     }
 
@@ -358,34 +1686,112 @@ fn create_skinned_aabb_component(
     )
     .entered();
 
+    // The joint hierarchy is only needed for simplification and the weight
+    // threshold.
+    let joint_parents = (settings.simplification.is_some()
+        || settings.lod.is_some()
+        || (settings.min_joint_weight > 0.0))
+        .then(joint_parents);
+
     let asset = skinned_aabb_assets.add(create_skinned_aabb_asset(
         mesh,
         mesh_handle.id(),
         inverse_bindposes,
         inverse_bindposes_handle.id(),
+        settings,
+        influence_attributes,
+        joint_parents.as_deref(),
     ));
 
-    Some(SkinnedAabb { asset })
+    Some(SkinnedAabb {
+        asset,
+        ..Default::default()
+    })
 }
 
+// Return the index of each joint's parent joint, or None if the joint has no
+// ancestor that's also a joint.
+fn joint_parents(skinned_mesh: &SkinnedMesh, parents: &Query<&ChildOf>) -> Vec<Option<usize>> {
+    let joint_entity_to_index = skinned_mesh
+        .joints
+        .iter()
+        .enumerate()
+        .map(|(joint_index, &joint_entity)| (joint_entity, joint_index))
+        .collect::<EntityHashMap<_>>();
+
+    skinned_mesh
+        .joints
+        .iter()
+        .map(|&joint_entity| {
+            parents
+                .iter_ancestors(joint_entity)
+                .find_map(|ancestor| joint_entity_to_index.get(&ancestor).copied())
+        })
+        .collect()
+}
+
+// Skip entities that already have a `SkinnedAabb` or failed validation.
+type CreateSkinnedAabbsFilter = (Without<SkinnedAabb>, Without<SkinnedAabbDegraded>);
+
 // If any entities have `Mesh3d` and `SkinnedMesh` components but no
 // `SkinnedAabb` component, try to create one.
+#[allow(clippy::too_many_arguments)]
 pub fn create_skinned_aabbs(
     mut commands: Commands,
     mut skinned_aabb_assets: ResMut<Assets<SkinnedAabbAsset>>,
     mesh_assets: Res<Assets<Mesh>>,
     inverse_bindposes_assets: Res<Assets<SkinnedMeshInverseBindposes>>,
-    query: Query<(Entity, &Mesh3d, &SkinnedMesh), Without<SkinnedAabb>>,
+    query: Query<(Entity, &Mesh3d, &SkinnedMesh), CreateSkinnedAabbsFilter>,
+    parents: Query<&ChildOf>,
+    names: Query<&Name>,
+    settings: Res<SkinnedAabbPluginSettings>,
+    influences: Res<SkinnedAabbInfluences>,
+    joint_padding_settings: Res<SkinnedAabbJointPadding>,
 ) {
     for (entity, mesh, skinned_mesh) in &query {
-        if let Some(skinned_aabb) = create_skinned_aabb_component(
+        let error = inverse_bindposes_assets
+            .get(&skinned_mesh.inverse_bindposes)
+            .and_then(|inverse_bindposes| {
+                validate_skinned_mesh(skinned_mesh, inverse_bindposes).err()
+            });
+
+        if let Some(error) = error
+            && !settings.allow_mismatched_joints
+        {
+            warn!(
+                "Skinned mesh entity {entity} is invalid and won't get a skinned AABB: {error:?}."
+            );
+            commands.entity(entity).insert(SkinnedAabbDegraded(error));
+            continue;
+        }
+
+        if let Some(mut skinned_aabb) = create_skinned_aabb_component(
             &mut skinned_aabb_assets,
             &mesh_assets,
             &mesh.0,
             &inverse_bindposes_assets,
             &skinned_mesh.inverse_bindposes,
+            &settings,
+            &influences.0,
+            || joint_parents(skinned_mesh, &parents),
         ) {
-            commands.entity(entity).insert(skinned_aabb);
+            if let Some(asset) = skinned_aabb_assets.get(&skinned_aabb.asset) {
+                skinned_aabb.joint_padding = lod_joint_padding(
+                    asset,
+                    joint_padding(skinned_mesh, &names, &joint_padding_settings.0),
+                );
+            }
+
+            let mut entity_commands = commands.entity(entity);
+
+            entity_commands.insert(skinned_aabb);
+
+            if let Some(error) = error {
+                warn!(
+                    "Skinned mesh entity {entity} is invalid, so its skinned AABB will only bound the joints that resolve: {error:?}."
+                );
+                entity_commands.insert(SkinnedAabbDegraded(error));
+            }
         }
     }
 }
@@ -443,74 +1849,567 @@ pub fn aabb_transformed_by(input: PackedAabb3d, transform: Affine3A) -> Aabb3d {
     Aabb3d { min, max }
 }
 
+// Return `aabb` grown by enough to cover rounding errors in values of the given
+// magnitude.
+fn rounded_outward(aabb: Aabb3d, magnitude: Vec3A) -> Aabb3d {
+    let margin = magnitude * ROUNDING_MARGIN;
+
+    Aabb3d {
+        min: aabb.min - margin,
+        max: aabb.max + margin,
+    }
+}
+
+// Return the largest magnitude of the terms summed when applying `transforms`
+// in order to a point with components up to `extent`. This bounds the rounding
+// error of each step, including when the transforms are combined beforehand.
+fn rounding_magnitude(extent: Vec3A, transforms: &[Affine3A]) -> Vec3A {
+    transforms.iter().fold(extent, |extent, transform| {
+        let rs = transform.matrix3;
+
+        transform.translation.abs()
+            + (rs.x_axis.abs() * extent.x)
+            + (rs.y_axis.abs() * extent.y)
+            + (rs.z_axis.abs() * extent.z)
+    })
+}
+
+fn extent(aabb: Aabb3d) -> Vec3A {
+    aabb.min.abs().max(aabb.max.abs())
+}
+
+// Convert to an `Aabb`, optionally making sure that the center and half extents
+// representation doesn't round the minimum and maximum inwards.
+fn aabb_from_min_max(aabb: Aabb3d, conservative_rounding: bool) -> Aabb {
+    let mut result = Aabb::from_min_max(Vec3::from(aabb.min), Vec3::from(aabb.max));
+
+    if conservative_rounding {
+        result.half_extents += (result.center.abs() + result.half_extents.abs()) * f32::EPSILON;
+    }
+
+    result
+}
+
 // Given a skinned mesh and world-space joints, return the entity-space AABB.
-// Returns None if no joints were found or the asset was not found.
+// Returns None if no joints or unweighted vertices were found or the asset was
+// not found, or if there was an issue.
+#[allow(clippy::too_many_arguments)]
 fn get_skinned_aabb(
     component: &SkinnedAabb,
     joints: &Query<&GlobalTransform>,
     assets: &Assets<SkinnedAabbAsset>,
     skinned_mesh: &SkinnedMesh,
     world_from_entity: &GlobalTransform,
+    lod: usize,
+    conservative_rounding: bool,
+    issue: &mut Option<SkinnedAabbIssue>,
 ) -> Option<Aabb> {
+    let entity_aabb = skinned_aabb_in_space(
+        component,
+        joints,
+        assets,
+        skinned_mesh,
+        world_from_entity,
+        None,
+        lod,
+        conservative_rounding,
+        issue,
+    )?;
+
+    let entity_aabb = aabb_from_min_max(entity_aabb, conservative_rounding);
+
+    if !entity_aabb.center.is_finite() || !entity_aabb.half_extents.is_finite() {
+        *issue = Some(SkinnedAabbIssue::NonFiniteAabb);
+        return None;
+    }
+
+    Some(entity_aabb)
+}
+
+// Same as `get_skinned_aabb`, but returns the AABB in the space given by
+// `space_from_world`. This is tighter than transforming the entity-space AABB
+// to that space.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn get_skinned_aabb_in_space(
+    component: &SkinnedAabb,
+    joints: &Query<&GlobalTransform>,
+    assets: &Assets<SkinnedAabbAsset>,
+    skinned_mesh: &SkinnedMesh,
+    world_from_entity: &GlobalTransform,
+    space_from_world: Affine3A,
+    lod: usize,
+    conservative_rounding: bool,
+    issue: &mut Option<SkinnedAabbIssue>,
+) -> Option<Aabb3d> {
+    let space_aabb = skinned_aabb_in_space(
+        component,
+        joints,
+        assets,
+        skinned_mesh,
+        world_from_entity,
+        Some(space_from_world),
+        lod,
+        conservative_rounding,
+        issue,
+    )?;
+
+    if !space_aabb.min.is_finite() || !space_aabb.max.is_finite() {
+        *issue = Some(SkinnedAabbIssue::NonFiniteAabb);
+        return None;
+    }
+
+    Some(space_aabb)
+}
+
+// Return the AABB of the skinned mesh in the space given by `space_from_world`,
+// or in entity space if that's None.
+#[allow(clippy::too_many_arguments)]
+fn skinned_aabb_in_space(
+    component: &SkinnedAabb,
+    joints: &Query<&GlobalTransform>,
+    assets: &Assets<SkinnedAabbAsset>,
+    skinned_mesh: &SkinnedMesh,
+    world_from_entity: &GlobalTransform,
+    space_from_world: Option<Affine3A>,
+    lod: usize,
+    conservative_rounding: bool,
+    issue: &mut Option<SkinnedAabbIssue>,
+) -> Option<Aabb3d> {
     let asset = assets.get(&component.asset)?;
     let world_from_entity = world_from_entity.affine();
-    let num_aabbs = asset.num_aabbs();
+    let (aabbs, aabb_index_to_joint_index) = asset.lod(lod);
 
-    if num_aabbs == 0 {
+    if aabbs.is_empty() && asset.unweighted_aabb.is_none() {
         return None;
     }
 
-    let entity_from_world = world_from_entity.inverse();
+    let Some(entity_from_world) = entity_from_world(world_from_entity) else {
+        *issue = Some(SkinnedAabbIssue::InvalidEntityTransform);
+        return None;
+    };
+
+    let entity_space = space_from_world.is_none();
+    let space_from_world = space_from_world.unwrap_or(entity_from_world);
+
+    let joints_aabb = match asset.joint_weight_sum_range {
+        None => merged_joint_aabbs(
+            aabbs,
+            aabb_index_to_joint_index,
+            skinned_mesh,
+            joints,
+            component.lod_joint_padding(lod),
+            space_from_world,
+            conservative_rounding,
+            issue,
+        ),
+        Some((min_scale, max_scale)) => {
+            // Unnormalized weights scale the vertices towards the world origin,
+            // so we have to scale the world-space AABB before transforming it
+            // to the target space.
+            merged_joint_aabbs(
+                aabbs,
+                aabb_index_to_joint_index,
+                skinned_mesh,
+                joints,
+                component.lod_joint_padding(lod),
+                Affine3A::IDENTITY,
+                conservative_rounding,
+                issue,
+            )
+            .map(|world_aabb| {
+                let mut scaled = aabb_scaled_by_range(world_aabb, min_scale, max_scale);
+
+                if conservative_rounding {
+                    scaled = rounded_outward(scaled, extent(scaled));
+                }
+
+                if space_from_world == Affine3A::IDENTITY {
+                    return scaled;
+                }
+
+                let space_aabb = aabb_transformed_by(scaled.into(), space_from_world);
+
+                match conservative_rounding {
+                    true => rounded_outward(
+                        space_aabb,
+                        rounding_magnitude(extent(scaled), &[space_from_world]),
+                    ),
+                    false => space_aabb,
+                }
+            })
+        }
+    };
+
+    // Unweighted vertices are stored in entity space.
+    let unweighted_aabb = asset.unweighted_aabb.map(|unweighted_aabb| {
+        if entity_space {
+            return Aabb3d::from(unweighted_aabb);
+        }
+
+        let space_aabb = aabb_transformed_by(unweighted_aabb, space_from_world * world_from_entity);
+
+        match conservative_rounding {
+            true => rounded_outward(
+                space_aabb,
+                rounding_magnitude(
+                    extent(unweighted_aabb.into()),
+                    &[world_from_entity, space_from_world],
+                ),
+            ),
+            false => space_aabb,
+        }
+    });
+
+    match (joints_aabb, unweighted_aabb) {
+        (Some(l), Some(r)) => Some(l.merge(&r)),
+        (l, r) => l.or(r),
+    }
+}
+
+// Return the half size of a box that contains an entity-space padding of
+// `padding` on each side, transformed by `space_from_entity`.
+pub(crate) fn padding_in_space(padding: f32, space_from_entity: Affine3A) -> Vec3A {
+    let rs = space_from_entity.matrix3;
+
+    (rs.x_axis.abs() + rs.y_axis.abs() + rs.z_axis.abs()) * padding
+}
+
+// Upper bound on how much `m` can scale a length. The spectral norm is tighter,
+// but the Frobenius norm is simple and always larger.
+pub(crate) fn max_scale(m: Mat3A) -> f32 {
+    (m.x_axis.length_squared() + m.y_axis.length_squared() + m.z_axis.length_squared()).sqrt()
+}
 
-    let mut entity_aabb = Aabb3d {
+// Return the merged AABB of all joints, in the space given by
+// `space_from_world`. Returns None if no joints were found. Joints with
+// non-finite transforms are skipped and reported through `issue`.
+#[allow(clippy::too_many_arguments)]
+fn merged_joint_aabbs(
+    aabbs: &[PackedAabb3d],
+    aabb_index_to_joint_index: &[JointIndex],
+    skinned_mesh: &SkinnedMesh,
+    joints: &Query<&GlobalTransform>,
+    joint_padding: &[f32],
+    space_from_world: Affine3A,
+    conservative_rounding: bool,
+    issue: &mut Option<SkinnedAabbIssue>,
+) -> Option<Aabb3d> {
+    let mut merged = Aabb3d {
         min: Vec3A::MAX,
         max: Vec3A::MIN,
     };
 
-    for aabb_index in 0..num_aabbs {
-        if let Some(world_from_joint) = asset.world_from_joint(aabb_index, skinned_mesh, joints) {
-            let entity_from_joint = entity_from_world * world_from_joint;
-            let joint_aabb = aabb_transformed_by(asset.aabb(aabb_index), entity_from_joint);
+    for (&aabb, &joint_index) in aabbs.iter().zip(aabb_index_to_joint_index) {
+        if let Some(world_from_joint) = world_from_joint_index(joint_index, skinned_mesh, joints) {
+            if !world_from_joint.is_finite() {
+                *issue = Some(SkinnedAabbIssue::InvalidJointTransform);
+                continue;
+            }
+
+            let aabb = padded_joint_aabb(aabb, joint_padding, joint_index);
 
-            entity_aabb = entity_aabb.merge(&joint_aabb);
+            let space_from_joint = space_from_world * world_from_joint;
+            let mut joint_aabb = aabb_transformed_by(aabb, space_from_joint);
+
+            if conservative_rounding {
+                joint_aabb = rounded_outward(
+                    joint_aabb,
+                    rounding_magnitude(extent(aabb.into()), &[world_from_joint, space_from_world]),
+                );
+            }
+
+            merged = merged.merge(&joint_aabb);
         }
     }
 
     // If min > max then no joints were found.
-    if entity_aabb.min.x > entity_aabb.max.x {
+    if merged.min.x > merged.max.x {
         return None;
     }
 
-    Some(Aabb::from_min_max(
-        Vec3::from(entity_aabb.min),
-        Vec3::from(entity_aabb.max),
-    ))
+    Some(merged)
 }
 
-pub fn update_skinned_aabbs(
-    mut query: Query<(&mut Aabb, &SkinnedAabb, &SkinnedMesh, &GlobalTransform)>,
-    joints: Query<&GlobalTransform>,
-    assets: Res<Assets<SkinnedAabbAsset>>,
-    settings: Res<SkinnedAabbPluginSettings>,
-) {
+// Return an AABB that contains `aabb` scaled towards the origin by any factor
+// in the range `[min_scale, max_scale]`.
+fn aabb_scaled_by_range(aabb: Aabb3d, min_scale: f32, max_scale: f32) -> Aabb3d {
+    let a = aabb.min * min_scale;
+    let b = aabb.min * max_scale;
+    let c = aabb.max * min_scale;
+    let d = aabb.max * max_scale;
+
+    Aabb3d {
+        min: a.min(b).min(c).min(d),
+        max: a.max(b).max(c).max(d),
+    }
+}
+
+// Entities with precomputed clip bounds are updated by
+// `update_skinned_aabbs_from_clips` instead.
+#[cfg(feature = "animation")]
+type UpdateSkinnedAabbsFilter = Without<clip::SkinnedAabbClipBounds>;
+#[cfg(not(feature = "animation"))]
+type UpdateSkinnedAabbsFilter = ();
+
+// Entities whose issues are kept in `SkinnedAabbDiagnostics`.
+#[cfg(feature = "animation")]
+type DiagnosedFilter = bevy_ecs::query::Or<(With<SkinnedAabb>, With<clip::SkinnedAabbClipBounds>)>;
+#[cfg(not(feature = "animation"))]
+type DiagnosedFilter = With<SkinnedAabb>;
+
+type UpdateSkinnedBoundsData<T> = (
+    Entity,
+    &'static mut T,
+    &'static SkinnedAabb,
+    &'static SkinnedMesh,
+    &'static GlobalTransform,
+    Option<&'static SkinnedAabbPadding>,
+    Option<&'static mut GlobalSkinnedAabb>,
+    Option<(
+        &'static mut PreviousSkinnedAabb,
+        &'static mut SweptSkinnedAabb,
+    )>,
+    Has<Aabb>,
+);
+
+#[derive(SystemParam)]
+pub struct UpdateSkinnedBoundsParams<'w, 's, T: SkinnedBoundsTarget> {
+    query: Query<'w, 's, UpdateSkinnedBoundsData<T>, UpdateSkinnedAabbsFilter>,
+    joints: Query<'w, 's, &'static GlobalTransform>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    assets: Res<'w, Assets<SkinnedAabbAsset>>,
+    settings: Res<'w, SkinnedAabbPluginSettings>,
+    diagnostics: ResMut<'w, SkinnedAabbDiagnostics>,
+    skinned: Query<'w, 's, (), DiagnosedFilter>,
+    parallel_issues: Local<'s, Parallel<Vec<(Entity, SkinnedAabbIssue)>>>,
+    joint_batches: Local<'s, JointBatches<T>>,
+}
+
+// Buffers for `SkinnedAabbBatching::Joints`, kept between updates so they're
+// only allocated when the number of entities grows.
+struct JointBatches<T: SkinnedBoundsTarget> {
+    items: Vec<Option<QueryItem<'static, 'static, UpdateSkinnedBoundsData<T>>>>,
+    batch_sizes: Vec<usize>,
+}
+
+impl<T: SkinnedBoundsTarget> Default for JointBatches<T> {
+    fn default() -> Self {
+        JointBatches {
+            items: Vec::new(),
+            batch_sizes: Vec::new(),
+        }
+    }
+}
+
+// Return the positions of the active cameras, or nothing if they're not needed
+// for levels of detail.
+pub(crate) fn lod_camera_positions(
+    settings: &SkinnedAabbPluginSettings,
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+) -> Vec<Vec3A> {
+    match settings.lod {
+        Some(_) => cameras
+            .iter()
+            .filter(|(camera, _)| camera.is_active)
+            .map(|(_, world_from_camera)| world_from_camera.translation_vec3a())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// Return the level of detail for an entity from the distance to the nearest
+// camera. Uses the most detailed level if there's no cameras.
+pub(crate) fn lod_at(
+    settings: &SkinnedAabbPluginSettings,
+    camera_positions: &[Vec3A],
+    world_from_entity: &GlobalTransform,
+) -> usize {
+    settings.lod.map_or(0, |lod| {
+        let position = world_from_entity.translation_vec3a();
+
+        camera_positions
+            .iter()
+            .map(|camera_position| camera_position.distance(position))
+            .reduce(f32::min)
+            .map_or(0, |distance| lod.lod(distance))
+    })
+}
+
+// Update the `Aabb` of entities with `SkinnedAabb`.
+pub fn update_skinned_aabbs(params: UpdateSkinnedBoundsParams<Aabb>) {
+    update_skinned_bounds(params);
+}
+
+// Update the `SkinnedBoundsTarget` of entities with `SkinnedAabb`.
+pub fn update_skinned_bounds<T: SkinnedBoundsTarget>(params: UpdateSkinnedBoundsParams<T>) {
+    let UpdateSkinnedBoundsParams {
+        mut query,
+        joints,
+        cameras,
+        assets,
+        settings,
+        mut diagnostics,
+        skinned,
+        mut parallel_issues,
+        mut joint_batches,
+    } = params;
+
+    let camera_positions = lod_camera_positions(&settings, &cameras);
+
     // Awkward closure so we don't have to duplicate the parallel/non-parallel paths.
     // TODO: Urgh. Alternatives?
-    let update =
-        |(mut entity_aabb, skinned_aabb, skinned_mesh, world_from_entity): (Mut<Aabb>, _, _, _)| {
-            if let Some(updated) = get_skinned_aabb(
+    #[allow(clippy::type_complexity)]
+    let update = |(
+        entity,
+        mut target,
+        skinned_aabb,
+        skinned_mesh,
+        world_from_entity,
+        padding,
+        global_aabb,
+        swept_aabb,
+        has_aabb,
+    ): (
+        Entity,
+        Mut<T>,
+        _,
+        _,
+        &GlobalTransform,
+        Option<&SkinnedAabbPadding>,
+        Option<Mut<GlobalSkinnedAabb>>,
+        Option<(Mut<PreviousSkinnedAabb>, Mut<SweptSkinnedAabb>)>,
+        bool,
+    )| {
+        let lod = lod_at(&settings, &camera_positions, world_from_entity);
+
+        let mut issue = None;
+
+        let updated = get_skinned_aabb(
+            skinned_aabb,
+            &joints,
+            &assets,
+            skinned_mesh,
+            world_from_entity,
+            lod,
+            settings.conservative_rounding,
+            &mut issue,
+        );
+
+        let padding = entity_padding(&settings, padding);
+
+        let owns_world_aabbs = T::WRITES_WORLD_BOUNDS_WITH_AABB || !has_aabb;
+
+        if owns_world_aabbs && (global_aabb.is_some() || swept_aabb.is_some()) {
+            let world_aabb = get_skinned_aabb_in_space(
                 skinned_aabb,
                 &joints,
                 &assets,
                 skinned_mesh,
                 world_from_entity,
-            ) {
-                *entity_aabb = updated;
+                Affine3A::IDENTITY,
+                lod,
+                settings.conservative_rounding,
+                &mut issue,
+            )
+            .map(|world_aabb| {
+                world_aabb.grow(padding_in_space(padding, world_from_entity.affine()))
+            });
+
+            if let Some(mut global_aabb) = global_aabb {
+                global_aabb.0 = world_aabb;
             }
-        };
+
+            if let Some((mut previous_aabb, mut swept_aabb)) = swept_aabb {
+                swept_aabb.0 = match (previous_aabb.0, world_aabb) {
+                    (Some(previous), Some(current)) => Some(previous.merge(&current)),
+                    (_, current) => current,
+                };
+
+                previous_aabb.0 = world_aabb;
+            }
+        }
+
+        if let Some(issue) = issue {
+            parallel_issues.borrow_local_mut().push((entity, issue));
+        }
+
+        if let Some(mut updated) = updated {
+            updated.half_extents += Vec3A::splat(padding);
+
+            let current = settings.hysteresis.zip(target.skinned_bounds());
+
+            match current {
+                Some((hysteresis, current)) => {
+                    if let Some(updated) = hysteresis.update(&current, &updated) {
+                        target.set_skinned_bounds(updated, world_from_entity);
+                    }
+                }
+                None => target.set_skinned_bounds(updated, world_from_entity),
+            }
+        }
+    };
 
     if settings.parallel {
-        query.par_iter_mut().for_each(update);
+        if let SkinnedAabbBatching::Joints(joints_per_batch) = settings.batching {
+            // `par_iter_mut` only supports batches with the same number of
+            // entities, so spawn the tasks ourselves. Each task takes the items
+            // of its batch out of a shared buffer.
+            let JointBatches { items, batch_sizes } = &mut *joint_batches;
+
+            let mut batch_items = reuse_vec(core::mem::take(items));
+
+            batch_items.extend(query.iter_mut().map(Some));
+
+            batch_sizes_by_joints(
+                batch_items
+                    .iter()
+                    .flatten()
+                    .map(|(_, _, _, skinned_mesh, _, _, _, _, _)| skinned_mesh.joints.len()),
+                joints_per_batch.max(1),
+                batch_sizes,
+            );
+
+            if batch_sizes.len() <= 1 {
+                batch_items
+                    .iter_mut()
+                    .filter_map(Option::take)
+                    .for_each(&update);
+            } else {
+                let update = &update;
+
+                ComputeTaskPool::get().scope(|scope| {
+                    let mut remaining = batch_items.as_mut_slice();
+
+                    for &batch_size in batch_sizes.iter() {
+                        let (batch, rest) = remaining.split_at_mut(batch_size);
+
+                        remaining = rest;
+
+                        scope.spawn(async move {
+                            batch.iter_mut().filter_map(Option::take).for_each(update);
+                        });
+                    }
+                });
+            }
+
+            *items = reuse_vec(batch_items);
+        } else {
+            query
+                .par_iter_mut()
+                .batching_strategy(settings.batching_strategy())
+                .for_each(update);
+        }
     } else {
         query.iter_mut().for_each(update);
     }
+
+    // Entities with other targets or clip bounds are updated by other
+    // systems, so leave their issues alone unless they've lost their
+    // `SkinnedAabb` and clip bounds.
+
+    diagnostics.replace_issues(
+        |entity| query.contains(entity),
+        |entity| skinned.contains(entity),
+        parallel_issues.drain(),
+    );
 }
@@ -0,0 +1,196 @@
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    message::{Message, MessageWriter},
+    query::With,
+    system::{Query, SystemParam},
+};
+use bevy_math::{
+    Vec3A,
+    bounding::{Aabb3d, IntersectsVolume},
+};
+
+use crate::{
+    JointIndex, SkinnedAabb, SkinnedJointBound, SkinnedJointBounds, entity_world_aabb,
+    raycast::EntityBoundsData,
+};
+
+// A pair of overlapping joints from two skinned meshes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SkinnedJointOverlap {
+    // Index into `SkinnedMesh::joints` of the first entity.
+    pub joint_index_a: JointIndex,
+    pub joint_entity_a: Entity,
+
+    // Index into `SkinnedMesh::joints` of the second entity.
+    pub joint_index_b: JointIndex,
+    pub joint_entity_b: Entity,
+}
+
+// Marks entities with `SkinnedAabb` whose joints are tested against each other
+// by `send_hitbox_overlaps`.
+#[derive(Component, Copy, Clone, Debug, Default)]
+pub struct SkinnedAabbHitbox;
+
+// Sent by `send_hitbox_overlaps` for each pair of entities with
+// `SkinnedAabbHitbox` that have overlapping joints.
+#[derive(Message, Clone, Debug)]
+pub struct SkinnedAabbHitboxOverlap {
+    pub entity_a: Entity,
+    pub entity_b: Entity,
+    pub joints: Vec<SkinnedJointOverlap>,
+}
+
+// A box transformed into world space, which can be scaled or sheared.
+struct Parallelepiped {
+    center: Vec3A,
+
+    // Vectors from the center to the middle of three adjacent faces.
+    half_edges: [Vec3A; 3],
+}
+
+impl Parallelepiped {
+    fn new(bound: &SkinnedJointBound) -> Self {
+        let aabb = Aabb3d::from(bound.aabb);
+        let half_size = (aabb.max - aabb.min) * 0.5;
+        let rs = bound.world_from_joint.matrix3;
+
+        Parallelepiped {
+            center: bound
+                .world_from_joint
+                .transform_point3a((aabb.min + aabb.max) * 0.5),
+            half_edges: [
+                rs.x_axis * half_size.x,
+                rs.y_axis * half_size.y,
+                rs.z_axis * half_size.z,
+            ],
+        }
+    }
+
+    // Return the half length of the projection onto `axis`.
+    fn radius(&self, axis: Vec3A) -> f32 {
+        self.half_edges
+            .iter()
+            .map(|half_edge| half_edge.dot(axis).abs())
+            .sum()
+    }
+
+    // Return the face normals, along with the edges that they're made from.
+    fn face_normals(&self) -> [(Vec3A, Vec3A, Vec3A); 3] {
+        let [x, y, z] = self.half_edges;
+
+        [(y, z, y.cross(z)), (z, x, z.cross(x)), (x, y, x.cross(y))]
+    }
+}
+
+// Return true if the oriented boxes of two joints overlap.
+//
+// This uses the separating axis theorem, which works for scaled and sheared
+// boxes as well as rotated ones. Boxes that touch are considered overlapping.
+pub fn joint_bounds_overlap(a: &SkinnedJointBound, b: &SkinnedJointBound) -> bool {
+    let a = Parallelepiped::new(a);
+    let b = Parallelepiped::new(b);
+    let offset = b.center - a.center;
+
+    let edge_axes = a.half_edges.into_iter().flat_map(|a_edge| {
+        b.half_edges
+            .map(|b_edge| (a_edge, b_edge, a_edge.cross(b_edge)))
+    });
+
+    let face_axes = a.face_normals().into_iter().chain(b.face_normals());
+
+    face_axes.chain(edge_axes).all(|(l, r, axis)| {
+        // Skip axes from parallel or degenerate edges. The remaining axes are
+        // enough to find any separation.
+        if axis.length_squared() <= 1.0e-12 * l.length_squared() * r.length_squared() {
+            return true;
+        }
+
+        offset.dot(axis).abs() <= a.radius(axis) + b.radius(axis)
+    })
+}
+
+// Finds overlapping joints between entities with `SkinnedAabb`. Each entity's
+// bounds are tested first, as described by `raycast::EntityBoundsData`.
+#[derive(SystemParam)]
+pub struct SkinnedAabbOverlap<'w, 's> {
+    pub query: Query<'w, 's, EntityBoundsData, With<SkinnedAabb>>,
+    pub joint_bounds: SkinnedJointBounds<'w, 's>,
+}
+
+impl SkinnedAabbOverlap<'_, '_> {
+    // Return the world-space bounds of the entity, or an infinite AABB if the
+    // entity has no bounds to test against.
+    fn world_aabb(&self, entity: Entity) -> Option<Aabb3d> {
+        let (_, aabb, global_aabb, world_from_entity) = self.query.get(entity).ok()?;
+
+        Some(
+            entity_world_aabb(aabb, global_aabb, world_from_entity).unwrap_or(Aabb3d {
+                min: Vec3A::NEG_INFINITY,
+                max: Vec3A::INFINITY,
+            }),
+        )
+    }
+
+    // Return each pair of overlapping joints between `a` and `b`.
+    pub fn overlapping_joints(&self, a: Entity, b: Entity) -> Vec<SkinnedJointOverlap> {
+        let (Some(a_aabb), Some(b_aabb)) = (self.world_aabb(a), self.world_aabb(b)) else {
+            return Vec::new();
+        };
+
+        if !a_aabb.intersects(&b_aabb) {
+            return Vec::new();
+        }
+
+        // Only keep joints that touch the other entity's AABB, and then test
+        // their world-space AABBs before the more expensive oriented test.
+
+        let nearby = |entity: Entity, other_aabb: Aabb3d| {
+            self.joint_bounds
+                .iter(entity)
+                .map(|bound| (bound, bound.world_aabb()))
+                .filter(|(_, world_aabb)| world_aabb.intersects(&other_aabb))
+                .collect::<Vec<_>>()
+        };
+
+        let a_joints = nearby(a, b_aabb);
+        let b_joints = nearby(b, a_aabb);
+
+        let mut overlaps = Vec::new();
+
+        for (a_bound, a_world_aabb) in &a_joints {
+            for (b_bound, b_world_aabb) in &b_joints {
+                if a_world_aabb.intersects(b_world_aabb) && joint_bounds_overlap(a_bound, b_bound) {
+                    overlaps.push(SkinnedJointOverlap {
+                        joint_index_a: a_bound.joint_index,
+                        joint_entity_a: a_bound.joint_entity,
+                        joint_index_b: b_bound.joint_index,
+                        joint_entity_b: b_bound.joint_entity,
+                    });
+                }
+            }
+        }
+
+        overlaps
+    }
+}
+
+// Send a `SkinnedAabbHitboxOverlap` for each pair of entities with
+// `SkinnedAabbHitbox` that have overlapping joints.
+pub fn send_hitbox_overlaps(
+    hitboxes: Query<Entity, (With<SkinnedAabbHitbox>, With<SkinnedAabb>)>,
+    overlap: SkinnedAabbOverlap,
+    mut writer: MessageWriter<SkinnedAabbHitboxOverlap>,
+) {
+    for [entity_a, entity_b] in hitboxes.iter_combinations() {
+        let joints = overlap.overlapping_joints(entity_a, entity_b);
+
+        if !joints.is_empty() {
+            writer.write(SkinnedAabbHitboxOverlap {
+                entity_a,
+                entity_b,
+                joints,
+            });
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use bevy_app::{App, Plugin, PreUpdate};
+use bevy_camera::{
+    Camera,
+    visibility::{InheritedVisibility, RenderLayers, ViewVisibility},
+};
+use bevy_ecs::{
+    change_detection::{Res, ResMut},
+    component::Component,
+    entity::{Entity, EntityHashSet},
+    message::MessageWriter,
+    query::Has,
+    resource::Resource,
+    schedule::IntoScheduleConfigs,
+    system::Query,
+};
+use bevy_picking::{
+    Pickable, PickingSystems,
+    backend::{HitData, PointerHits, ray::RayMap},
+    pointer::PointerId,
+};
+use bevy_platform::collections::HashMap;
+
+use crate::raycast::{SkinnedAabbRayHit, SkinnedAabbRaycast};
+
+// A `bevy_picking` backend that hits entities with `SkinnedAabb` using their
+// joint bounds.
+//
+// The hit position is in world space, and there's no normal. The joint that
+// was hit is stored in `SkinnedAabbPickingHits`, since `HitData` has nowhere
+// to put it.
+//
+// Like `bevy_picking`'s mesh backend, entities are only hit if they're visible
+// as set by `SkinnedAabbPickingSettings::visibility` and share a `RenderLayers`
+// layer with the camera. Entities without visibility components count as
+// visible.
+//
+// Picking runs in `PreUpdate`, so the bounds are from the previous frame.
+#[derive(Default)]
+pub struct SkinnedAabbPickingPlugin;
+
+impl Plugin for SkinnedAabbPickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkinnedAabbPickingSettings>()
+            .init_resource::<SkinnedAabbPickingHits>()
+            .add_systems(PreUpdate, update_hits.in_set(PickingSystems::Backend));
+    }
+}
+
+#[derive(Resource, Copy, Clone, Default, Debug)]
+pub struct SkinnedAabbPickingSettings {
+    // If true, only cameras with `SkinnedAabbPickingCamera` and entities with
+    // `Pickable` are used. Defaults to false.
+    pub require_markers: bool,
+
+    // Which entities can be hit depending on their visibility. Defaults to
+    // `SkinnedAabbPickingVisibility::VisibleInView`.
+    pub visibility: SkinnedAabbPickingVisibility,
+}
+
+// Mirrors `bevy_picking`'s `RayCastVisibility`, which is only available with
+// its mesh picking feature.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum SkinnedAabbPickingVisibility {
+    // Hit entities regardless of their visibility.
+    Any,
+    // Hit entities whose `InheritedVisibility` is visible.
+    Visible,
+    // Hit entities whose `ViewVisibility` is visible in any view.
+    #[default]
+    VisibleInView,
+}
+
+// Marks cameras that are used for picking if
+// `SkinnedAabbPickingSettings::require_markers` is true.
+#[derive(Component, Copy, Clone, Default, Debug)]
+pub struct SkinnedAabbPickingCamera;
+
+// The joint hit by each pointer on each entity during the last picking update.
+#[derive(Resource, Default, Debug)]
+pub struct SkinnedAabbPickingHits {
+    pub hits: HashMap<(PointerId, Entity), SkinnedAabbRayHit>,
+}
+
+impl SkinnedAabbPickingHits {
+    // Return the nearest joint hit by `pointer` on `entity`, if any.
+    pub fn get(&self, pointer: PointerId, entity: Entity) -> Option<&SkinnedAabbRayHit> {
+        self.hits.get(&(pointer, entity))
+    }
+}
+
+type UpdateHitsTargetData = (
+    Option<&'static Pickable>,
+    Option<&'static InheritedVisibility>,
+    Option<&'static ViewVisibility>,
+    Option<&'static RenderLayers>,
+);
+
+// Cast each picking ray against the joint bounds and send `PointerHits`.
+pub fn update_hits(
+    settings: Res<SkinnedAabbPickingSettings>,
+    ray_map: Res<RayMap>,
+    cameras: Query<(
+        &Camera,
+        Has<SkinnedAabbPickingCamera>,
+        Option<&RenderLayers>,
+    )>,
+    targets: Query<UpdateHitsTargetData>,
+    raycast: SkinnedAabbRaycast,
+    mut picking_hits: ResMut<SkinnedAabbPickingHits>,
+    mut pointer_hits_writer: MessageWriter<PointerHits>,
+) {
+    picking_hits.hits.clear();
+
+    for (&ray_id, &ray) in ray_map.iter() {
+        let Ok((camera, marked_camera, camera_layers)) = cameras.get(ray_id.camera) else {
+            continue;
+        };
+
+        if settings.require_markers && !marked_camera {
+            continue;
+        }
+
+        // Entities and cameras without `RenderLayers` are on the default layer.
+        let camera_layers = camera_layers.cloned().unwrap_or_default();
+
+        let mut picks = Vec::<(Entity, HitData)>::new();
+        let mut picked = EntityHashSet::default();
+
+        // The hits are sorted by distance, so the first hit on each entity is
+        // the nearest.
+
+        for hit in raycast.cast_ray(ray) {
+            let Ok((pickable, inherited_visibility, view_visibility, layers)) =
+                targets.get(hit.entity)
+            else {
+                continue;
+            };
+
+            let visible = match settings.visibility {
+                SkinnedAabbPickingVisibility::Any => true,
+                SkinnedAabbPickingVisibility::Visible => {
+                    inherited_visibility.is_none_or(|visibility| visibility.get())
+                }
+                SkinnedAabbPickingVisibility::VisibleInView => {
+                    view_visibility.is_none_or(|visibility| visibility.get())
+                }
+            };
+
+            let layers_match = camera_layers.intersects(&layers.cloned().unwrap_or_default());
+
+            if (settings.require_markers && pickable.is_none())
+                || pickable.is_some_and(|pickable| !pickable.is_hoverable)
+                || !visible
+                || !layers_match
+                || !picked.insert(hit.entity)
+            {
+                continue;
+            }
+
+            // If the pointer is over multiple cameras then keep the hit from
+            // whichever camera came first.
+            picking_hits
+                .hits
+                .entry((ray_id.pointer, hit.entity))
+                .or_insert(hit);
+
+            picks.push((
+                hit.entity,
+                HitData::new(
+                    ray_id.camera,
+                    hit.distance,
+                    Some(ray.get_point(hit.distance)),
+                    None,
+                ),
+            ));
+
+            if pickable.is_some_and(|pickable| pickable.should_block_lower) {
+                break;
+            }
+        }
+
+        if !picks.is_empty() {
+            pointer_hits_writer.write(PointerHits::new(ray_id.pointer, picks, camera.order as f32));
+        }
+    }
+}
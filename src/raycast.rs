@@ -0,0 +1,122 @@
+use bevy_camera::primitives::Aabb;
+use bevy_ecs::{
+    entity::Entity,
+    query::With,
+    system::{Query, SystemParam},
+};
+use bevy_math::{Affine3A, Ray3d, Vec3A, bounding::Aabb3d};
+use bevy_transform::components::GlobalTransform;
+
+use crate::{GlobalSkinnedAabb, JointIndex, SkinnedAabb, SkinnedJointBounds, entity_world_aabb};
+
+// The world-space bounds of an entity, used to skip its joints if the bounds
+// miss. Each entity's `GlobalSkinnedAabb` is used if it has one, or else its
+// `Aabb`, so queries that use this are only as up to date as the last
+// `update_skinned_aabbs`.
+pub(crate) type EntityBoundsData = (
+    Entity,
+    Option<&'static Aabb>,
+    Option<&'static GlobalSkinnedAabb>,
+    &'static GlobalTransform,
+);
+
+// A ray hit on a joint of a skinned mesh.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkinnedAabbRayHit {
+    // The entity with the `SkinnedAabb`.
+    pub entity: Entity,
+
+    pub joint_entity: Entity,
+
+    // Index into `SkinnedMesh::joints`.
+    pub joint_index: JointIndex,
+
+    // Distance along the ray to where it enters the joint's box. Zero if the
+    // ray starts inside the box.
+    pub distance: f32,
+}
+
+// Casts rays against the joint bounds of entities with `SkinnedAabb`. Each
+// entity's bounds are tested first, as described by `EntityBoundsData`.
+#[derive(SystemParam)]
+pub struct SkinnedAabbRaycast<'w, 's> {
+    pub query: Query<'w, 's, EntityBoundsData, With<SkinnedAabb>>,
+    pub joint_bounds: SkinnedJointBounds<'w, 's>,
+}
+
+impl SkinnedAabbRaycast<'_, '_> {
+    // Return every joint hit by `ray`, sorted by distance.
+    pub fn cast_ray(&self, ray: Ray3d) -> Vec<SkinnedAabbRayHit> {
+        let mut hits = Vec::new();
+
+        for (entity, aabb, global_aabb, world_from_entity) in &self.query {
+            if let Some(world_aabb) = entity_world_aabb(aabb, global_aabb, world_from_entity)
+                && ray_box_distance(ray, world_aabb, Affine3A::IDENTITY).is_none()
+            {
+                continue;
+            }
+
+            hits.extend(self.joint_bounds.iter(entity).filter_map(|bound| {
+                let distance = ray_box_distance(ray, bound.aabb.into(), bound.world_from_joint)?;
+
+                Some(SkinnedAabbRayHit {
+                    entity,
+                    joint_entity: bound.joint_entity,
+                    joint_index: bound.joint_index,
+                    distance,
+                })
+            }));
+        }
+
+        hits.sort_by(|l, r| l.distance.total_cmp(&r.distance));
+
+        hits
+    }
+
+    // Return the nearest joint hit by `ray`, if any.
+    pub fn cast_ray_nearest(&self, ray: Ray3d) -> Option<SkinnedAabbRayHit> {
+        self.cast_ray(ray).into_iter().next()
+    }
+}
+
+// Return the distance along `ray` to where it enters the box given by `aabb`
+// and `world_from_box`, or None if it misses. The box can be scaled or sheared,
+// but if it's degenerate then it's treated as a miss.
+pub(crate) fn ray_box_distance(ray: Ray3d, aabb: Aabb3d, world_from_box: Affine3A) -> Option<f32> {
+    let box_from_world = world_from_box.inverse();
+
+    if !box_from_world.is_finite() {
+        return None;
+    }
+
+    // The direction isn't normalized after the transform, so distances along
+    // the transformed ray are the same as distances along the world ray.
+    let origin = box_from_world.transform_point3a(ray.origin.into());
+    let direction = box_from_world.transform_vector3a(Vec3A::from(*ray.direction));
+
+    // On axes that the ray doesn't move along, it's either always or never
+    // between the box's planes. Handle those separately, since dividing would
+    // give 0 * inf = NaN if the ray starts on a plane.
+    let inverse_direction = direction.recip();
+    let moving = inverse_direction.is_finite_mask();
+
+    if (!moving & (origin.cmplt(aabb.min) | origin.cmpgt(aabb.max))).any() {
+        return None;
+    }
+
+    let a = Vec3A::select(
+        moving,
+        (aabb.min - origin) * inverse_direction,
+        Vec3A::NEG_INFINITY,
+    );
+    let b = Vec3A::select(
+        moving,
+        (aabb.max - origin) * inverse_direction,
+        Vec3A::INFINITY,
+    );
+
+    let near = a.min(b).max_element().max(0.0);
+    let far = a.max(b).min_element();
+
+    (near <= far).then_some(near)
+}
@@ -0,0 +1,133 @@
+use bevy_asset::Assets;
+use bevy_camera::{Camera, primitives::Aabb};
+use bevy_ecs::{
+    change_detection::Res,
+    component::Component,
+    entity::Entity,
+    hierarchy::Children,
+    query::{With, Without},
+    system::Query,
+};
+use bevy_math::{
+    Affine3A,
+    bounding::{Aabb3d, BoundingVolume},
+};
+use bevy_mesh::skinning::SkinnedMesh;
+use bevy_transform::components::GlobalTransform;
+
+use crate::{
+    PackedAabb3d, SkinnedAabb, SkinnedAabbAsset, SkinnedAabbPadding, SkinnedAabbPluginSettings,
+    aabb_transformed_by, entity_padding, get_skinned_aabb_in_space, lod_at, lod_camera_positions,
+    padding_in_space,
+};
+
+// The union of the bounds of every skinned mesh and `SkinnedAabbAttachment`
+// that's a descendant of this entity, including the entity itself, in the
+// space of this entity. Updated by `update_skinned_aabb_roots`.
+//
+// Useful for streaming and levels of detail, where a glTF scene's skinned
+// meshes are deep in the hierarchy but the bounds are wanted for the whole
+// character. The AABB is None if there were no descendants with bounds.
+#[derive(Component, Copy, Clone, Debug, Default)]
+pub struct SkinnedAabbRoot(pub Option<Aabb3d>);
+
+// Marks a rigid mesh, like a sword or helmet parented to a joint, whose `Aabb`
+// is included in the bounds of any `SkinnedAabbRoot` it's a descendant of.
+//
+// The `Aabb` is transformed as is, so it should already cover the mesh. Bevy
+// calculates it automatically for regular meshes.
+#[derive(Component, Copy, Clone, Debug, Default)]
+pub struct SkinnedAabbAttachment;
+
+type RootMeshData = (
+    &'static SkinnedAabb,
+    &'static SkinnedMesh,
+    &'static GlobalTransform,
+    Option<&'static Aabb>,
+    Option<&'static SkinnedAabbPadding>,
+);
+
+type AttachmentFilter = (With<SkinnedAabbAttachment>, Without<SkinnedAabb>);
+
+// Update the bounds of entities with `SkinnedAabbRoot`.
+//
+// The bounds of each mesh are calculated from its joints directly in the
+// root's space, which is tighter than transforming the mesh's `Aabb`. Meshes
+// that can't be calculated from their joints fall back to their `Aabb`.
+// Attachments use their transformed `Aabb`.
+#[allow(clippy::too_many_arguments)]
+pub fn update_skinned_aabb_roots(
+    mut roots: Query<(Entity, &mut SkinnedAabbRoot, &GlobalTransform)>,
+    children: Query<&Children>,
+    meshes: Query<RootMeshData>,
+    attachments: Query<(&Aabb, &GlobalTransform), AttachmentFilter>,
+    joints: Query<&GlobalTransform>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    assets: Res<Assets<SkinnedAabbAsset>>,
+    settings: Res<SkinnedAabbPluginSettings>,
+) {
+    let camera_positions = lod_camera_positions(&settings, &cameras);
+
+    for (root, mut root_aabb, world_from_root) in &mut roots {
+        let root_from_world = world_from_root.affine().inverse();
+
+        if !root_from_world.is_finite() {
+            root_aabb.0 = None;
+            continue;
+        }
+
+        let mesh_aabb = |entity: Entity| {
+            let (skinned_aabb, skinned_mesh, world_from_entity, aabb, padding) =
+                meshes.get(entity).ok()?;
+
+            let root_from_entity = root_from_world * world_from_entity.affine();
+
+            let root_space_aabb = get_skinned_aabb_in_space(
+                skinned_aabb,
+                &joints,
+                &assets,
+                skinned_mesh,
+                world_from_entity,
+                root_from_world,
+                lod_at(&settings, &camera_positions, world_from_entity),
+                settings.conservative_rounding,
+                &mut None,
+            );
+
+            // The `Aabb` already includes the padding.
+            let Some(root_space_aabb) = root_space_aabb else {
+                return aabb.map(|aabb| entity_aabb_in_space(aabb, root_from_entity));
+            };
+
+            let padding = entity_padding(&settings, padding);
+
+            Some(root_space_aabb.grow(padding_in_space(padding, root_from_entity)))
+        };
+
+        let attachment_aabb = |entity: Entity| {
+            let (aabb, world_from_entity) = attachments.get(entity).ok()?;
+
+            Some(entity_aabb_in_space(
+                aabb,
+                root_from_world * world_from_entity.affine(),
+            ))
+        };
+
+        let aabbs = [root]
+            .into_iter()
+            .chain(children.iter_descendants(root))
+            .filter_map(|entity| mesh_aabb(entity).or_else(|| attachment_aabb(entity)));
+
+        root_aabb.0 = aabbs.reduce(|l, r| l.merge(&r));
+    }
+}
+
+fn entity_aabb_in_space(aabb: &Aabb, space_from_entity: Affine3A) -> Aabb3d {
+    aabb_transformed_by(
+        PackedAabb3d {
+            min: aabb.min().into(),
+            max: aabb.max().into(),
+        },
+        space_from_entity,
+    )
+}
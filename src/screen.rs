@@ -0,0 +1,223 @@
+use bevy_camera::Camera;
+use bevy_ecs::{
+    change_detection::Res,
+    entity::Entity,
+    system::{Query, SystemParam},
+};
+use bevy_math::{
+    Affine3A, BVec3A, Rect, Vec2, Vec3A, Vec4,
+    bounding::{Aabb3d, BoundingVolume},
+};
+use bevy_transform::components::GlobalTransform;
+
+use crate::{
+    SkinnedAabbPadding, SkinnedAabbPluginSettings, SkinnedJointBounds, entity_padding,
+    get_skinned_aabb_in_space, padding_in_space,
+};
+
+// The screen-space bounds of a skinned mesh as seen by a camera.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkinnedAabbScreenRect {
+    // Viewport rectangle in logical pixels, with the origin at the top left.
+    // Clamped to the camera's viewport.
+    pub rect: Rect,
+
+    // Range of distances in front of the camera, along its forward axis.
+    pub min_depth: f32,
+    pub max_depth: f32,
+}
+
+// Projects the joint bounds of entities with `SkinnedAabb` to the screen.
+//
+// This is tighter than projecting the entity's `Aabb`, since each joint's box
+// is projected with its own transform. It's only as up to date as the joint
+// transforms, so call it after transform propagation for the current frame.
+//
+// The joint boxes don't cover the entity's padding, or vertices with
+// unnormalized or zero weights. Meshes that have those project a single box
+// that's calculated in view space the same way as the `Aabb`, so the
+// rectangle is always conservative but can be looser.
+#[derive(SystemParam)]
+pub struct SkinnedAabbScreen<'w, 's> {
+    pub cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    pub entities: Query<
+        'w,
+        's,
+        (
+            &'static GlobalTransform,
+            Option<&'static SkinnedAabbPadding>,
+        ),
+    >,
+    pub joint_bounds: SkinnedJointBounds<'w, 's>,
+    pub settings: Res<'w, SkinnedAabbPluginSettings>,
+}
+
+impl SkinnedAabbScreen<'_, '_> {
+    // Return the screen-space bounds of `entity` as seen by `camera`, or None
+    // if it's off screen, behind the camera, or the camera has no viewport.
+    //
+    // Parts of the mesh behind the near plane are left out. The far plane and
+    // occlusion are ignored.
+    pub fn screen_rect(&self, camera: Entity, entity: Entity) -> Option<SkinnedAabbScreenRect> {
+        let (camera, world_from_camera) = self.cameras.get(camera).ok()?;
+        let viewport = camera.logical_viewport_rect()?;
+
+        let view_from_world = world_from_camera.affine().inverse();
+        let clip_from_view = camera.clip_from_view();
+
+        let mut ndc_min = Vec2::INFINITY;
+        let mut ndc_max = Vec2::NEG_INFINITY;
+        let mut min_depth = f32::INFINITY;
+        let mut max_depth = f32::NEG_INFINITY;
+
+        let mesh_box = self.view_space_mesh_box(entity, view_from_world)?;
+
+        let joint_boxes = self
+            .joint_bounds
+            .iter(entity)
+            .filter(|_| mesh_box.is_none())
+            .map(|bound| {
+                (
+                    Aabb3d::from(bound.aabb),
+                    view_from_world * bound.world_from_joint,
+                )
+            });
+
+        for (aabb, view_from_box) in mesh_box.into_iter().chain(joint_boxes) {
+            let corners = box_corners(aabb).map(|corner| {
+                let view = view_from_box.transform_point3a(corner);
+                let clip = clip_from_view * Vec4::from((view, 1.0));
+
+                (view, clip)
+            });
+
+            // The box clipped by the near plane is a convex shape whose corners
+            // are the box's corners in front of the plane, plus the points where
+            // the box's edges cross the plane. The projection of those corners
+            // covers the projection of the shape.
+
+            let mut add = |view: Vec3A, clip: Vec4| {
+                let ndc = clip.truncate().truncate() / clip.w;
+
+                ndc_min = ndc_min.min(ndc);
+                ndc_max = ndc_max.max(ndc);
+                min_depth = min_depth.min(-view.z);
+                max_depth = max_depth.max(-view.z);
+            };
+
+            for &(view, clip) in &corners {
+                if in_front_distance(clip) >= 0.0 {
+                    add(view, clip);
+                }
+            }
+
+            for (a, b) in BOX_EDGES {
+                let (view_a, clip_a) = corners[a];
+                let (view_b, clip_b) = corners[b];
+
+                let distance_a = in_front_distance(clip_a);
+                let distance_b = in_front_distance(clip_b);
+
+                if (distance_a < 0.0) != (distance_b < 0.0) {
+                    let t = distance_a / (distance_a - distance_b);
+
+                    add(view_a.lerp(view_b, t), clip_a.lerp(clip_b, t));
+                }
+            }
+        }
+
+        if !(ndc_min.is_finite() && ndc_max.is_finite()) {
+            return None;
+        }
+
+        // NDC has y up, while the viewport has y down.
+        let to_viewport =
+            |ndc: Vec2| (Vec2::new(ndc.x, -ndc.y) + 1.0) * 0.5 * viewport.size() + viewport.min;
+
+        let rect =
+            Rect::from_corners(to_viewport(ndc_min), to_viewport(ndc_max)).intersect(viewport);
+
+        if rect.is_empty() {
+            return None;
+        }
+
+        Some(SkinnedAabbScreenRect {
+            rect,
+            min_depth: min_depth.max(0.0),
+            max_depth,
+        })
+    }
+
+    // If the joint boxes of `entity` don't cover the mesh then return a single
+    // view-space box that does, with no transform. Returns Some(None) if the
+    // joint boxes can be used, or None if the entity can't be found.
+    fn view_space_mesh_box(
+        &self,
+        entity: Entity,
+        view_from_world: Affine3A,
+    ) -> Option<Option<(Aabb3d, Affine3A)>> {
+        let (skinned_aabb, skinned_mesh) = self.joint_bounds.query.get(entity).ok()?;
+        let asset = self.joint_bounds.assets.get(&skinned_aabb.asset)?;
+        let (world_from_entity, padding) = self.entities.get(entity).ok()?;
+
+        let padding = entity_padding(&self.settings, padding);
+
+        if padding == 0.0
+            && asset.joint_weight_sum_range.is_none()
+            && asset.unweighted_aabb.is_none()
+        {
+            return Some(None);
+        }
+
+        let view_aabb = get_skinned_aabb_in_space(
+            skinned_aabb,
+            &self.joint_bounds.joints,
+            &self.joint_bounds.assets,
+            skinned_mesh,
+            world_from_entity,
+            view_from_world,
+            0,
+            self.settings.conservative_rounding,
+            &mut None,
+        )?;
+
+        let view_from_entity = view_from_world * world_from_entity.affine();
+
+        Some(Some((
+            view_aabb.grow(padding_in_space(padding, view_from_entity)),
+            Affine3A::IDENTITY,
+        )))
+    }
+}
+
+// Signed distance in clip space from the near plane, positive in front. Bevy
+// uses reversed depth, so the near plane is where NDC depth is one.
+fn in_front_distance(clip: Vec4) -> f32 {
+    clip.w - clip.z
+}
+
+// Pairs of indices into `box_corners` that are joined by an edge.
+const BOX_EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+// Return the corners of `aabb`. Bit 0 of the index selects the max x, bit 1
+// the max y, and bit 2 the max z.
+fn box_corners(aabb: Aabb3d) -> [Vec3A; 8] {
+    core::array::from_fn(|index| {
+        let mask = BVec3A::new(index & 1 != 0, index & 2 != 0, index & 4 != 0);
+
+        Vec3A::select(mask, aabb.max, aabb.min)
+    })
+}
@@ -0,0 +1,187 @@
+use bevy_math::{
+    Affine3A, Mat4, Vec3A,
+    bounding::{Aabb3d, BoundingVolume},
+};
+
+use core::cmp::Reverse;
+
+use crate::{JointIndex, SkinnedAabbJointMerge, SkinnedAabbSimplification, max_scale};
+
+// Return a parent-space AABB that contains the child-space `aabb` for any
+// rotation of the child joint around its origin. See the assumptions in
+// `SkinnedAabbSimplification`.
+fn bound_in_parent(aabb: Aabb3d, parent_from_child: Affine3A) -> Aabb3d {
+    // Distance from the child's origin to the furthest corner of the AABB.
+    let radius = aabb.min.abs().max(aabb.max.abs()).length();
+
+    let scale = max_scale(parent_from_child.matrix3);
+
+    let center = parent_from_child.translation;
+    let half_size = Vec3A::splat(radius * scale);
+
+    Aabb3d {
+        min: center - half_size,
+        max: center + half_size,
+    }
+}
+
+// Return how much padding in the parent's space covers padding of one in the
+// child's space, after `bound_in_parent`. Padding the child's AABB moves its
+// corners by up to the length of the diagonal of a unit cube.
+fn padding_scale(parent_from_child: Affine3A) -> f32 {
+    3.0f32.sqrt() * max_scale(parent_from_child.matrix3)
+}
+
+fn volume(aabb: Aabb3d) -> f32 {
+    let size = aabb.max - aabb.min;
+
+    size.x * size.y * size.z
+}
+
+// Return `aabb` grown by `tolerance` times its largest half extent.
+fn grown(aabb: Aabb3d, tolerance: f32) -> Aabb3d {
+    let size = (aabb.half_size().max_element() * tolerance).max(0.0);
+
+    aabb.grow(Vec3A::splat(size))
+}
+
+fn contains(outer: Aabb3d, inner: Aabb3d) -> bool {
+    outer.min.cmple(inner.min).all() && outer.max.cmpge(inner.max).all()
+}
+
+// The skeleton of a skinned mesh in its bind pose.
+struct BindSkeleton<'a> {
+    joint_parents: &'a [Option<usize>],
+    parent_from_child: Box<[Option<Affine3A>]>,
+}
+
+impl<'a> BindSkeleton<'a> {
+    fn new(joint_parents: &'a [Option<usize>], inverse_bindposes: &[Mat4]) -> Self {
+        let parent_from_child = joint_parents
+            .iter()
+            .enumerate()
+            .map(|(child, parent)| {
+                let parent = (*parent)?;
+                let parent_from_model = inverse_bindposes.get(parent)?;
+                let model_from_child = inverse_bindposes.get(child)?.inverse();
+
+                Some(Affine3A::from_mat4(*parent_from_model * model_from_child))
+            })
+            .collect();
+
+        BindSkeleton {
+            joint_parents,
+            parent_from_child,
+        }
+    }
+
+    // Return the parent of `joint_index` and `aabb` transformed from the joint
+    // to the parent's space.
+    fn merge_target(&self, joint_index: usize, aabb: Aabb3d) -> Option<(usize, Aabb3d)> {
+        let parent = (*self.joint_parents.get(joint_index)?)?;
+        let parent_from_child = (*self.parent_from_child.get(joint_index)?)?;
+
+        Some((parent, bound_in_parent(aabb, parent_from_child)))
+    }
+
+    fn merge(&self, joint_index: usize, target_index: usize) -> SkinnedAabbJointMerge {
+        let padding_scale = self
+            .parent_from_child
+            .get(joint_index)
+            .copied()
+            .flatten()
+            .map_or(0.0, padding_scale);
+
+        SkinnedAabbJointMerge {
+            joint_index: joint_index as JointIndex,
+            target_index: target_index as JointIndex,
+            padding_scale,
+        }
+    }
+
+    fn depth(&self, joint_index: usize) -> usize {
+        let mut depth = 0;
+        let mut joint_index = joint_index;
+
+        while let Some(Some(parent)) = self.joint_parents.get(joint_index) {
+            joint_index = *parent;
+            depth += 1;
+
+            if depth > self.joint_parents.len() {
+                break;
+            }
+        }
+
+        depth
+    }
+}
+
+// Reduce the number of joint AABBs by merging them into their parents.
+//
+// `aabbs` and `joint_parents` are indexed by joint. Merged joints have their
+// AABB set to None. Returns the merges in the order they were made.
+pub(crate) fn simplify_joint_aabbs(
+    aabbs: &mut [Option<Aabb3d>],
+    joint_parents: &[Option<usize>],
+    inverse_bindposes: &[Mat4],
+    simplification: &SkinnedAabbSimplification,
+) -> Vec<SkinnedAabbJointMerge> {
+    let skeleton = BindSkeleton::new(joint_parents, inverse_bindposes);
+    let mut merges = Vec::new();
+
+    // First merge any joints that fit within their parent's AABB. Visit the
+    // deepest joints first so that small chains like fingers can collapse
+    // into a single AABB.
+
+    let mut joints_by_depth = (0..aabbs.len()).collect::<Vec<_>>();
+    joints_by_depth.sort_by_key(|&joint_index| Reverse(skeleton.depth(joint_index)));
+
+    for joint_index in joints_by_depth {
+        let Some(aabb) = aabbs[joint_index] else {
+            continue;
+        };
+
+        if let Some((target_index, target_aabb)) = skeleton.merge_target(joint_index, aabb)
+            && let Some(existing) = aabbs.get(target_index).copied().flatten()
+            && contains(grown(existing, simplification.tolerance), target_aabb)
+        {
+            aabbs[target_index] = Some(existing.merge(&target_aabb));
+            aabbs[joint_index] = None;
+            merges.push(skeleton.merge(joint_index, target_index));
+        }
+    }
+
+    // Then keep merging whichever joint adds the least volume until we're
+    // within the limit. If the parent has no AABB then the joint's AABB moves
+    // to the parent, which doesn't reduce the count immediately but lets
+    // siblings merge with it. Every merge either reduces the count or moves an
+    // AABB closer to the root, so the loop always terminates.
+
+    while aabbs.iter().flatten().count() > simplification.max_aabbs {
+        let cheapest = aabbs
+            .iter()
+            .enumerate()
+            .filter_map(|(joint_index, aabb)| {
+                let aabb = (*aabb)?;
+                let (target_index, target_aabb) = skeleton.merge_target(joint_index, aabb)?;
+                let existing = *aabbs.get(target_index)?;
+
+                let merged = existing.map_or(target_aabb, |e| e.merge(&target_aabb));
+                let cost = volume(merged) - existing.map_or(0.0, volume) - volume(aabb);
+
+                Some((cost, joint_index, target_index, merged))
+            })
+            .min_by(|l, r| l.0.total_cmp(&r.0));
+
+        let Some((_, joint_index, target_index, merged)) = cheapest else {
+            // No more joints can be merged.
+            break;
+        };
+
+        aabbs[target_index] = Some(merged);
+        aabbs[joint_index] = None;
+        merges.push(skeleton.merge(joint_index, target_index));
+    }
+
+    merges
+}
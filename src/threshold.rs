@@ -0,0 +1,184 @@
+use bevy_math::{
+    Mat3A, Mat4, Vec3A,
+    bounding::{Aabb3d, BoundingVolume},
+};
+
+use crate::{SkinningAttributes, is_referenced, max_scale};
+
+// The skeleton of a skinned mesh in its bind pose, used to bound how far apart
+// two joints can move.
+struct BindJoints<'a> {
+    joint_parents: &'a [Option<usize>],
+
+    // Model-space origin of each joint.
+    origins: Box<[Vec3A]>,
+
+    // Upper bound on how much each inverse bindpose scales a length.
+    scales: Box<[f32]>,
+}
+
+impl<'a> BindJoints<'a> {
+    fn new(joint_parents: &'a [Option<usize>], inverse_bindposes: &[Mat4]) -> Self {
+        let origins = inverse_bindposes
+            .iter()
+            .map(|inverse_bindpose| Vec3A::from(inverse_bindpose.inverse().w_axis.truncate()))
+            .collect();
+
+        let scales = inverse_bindposes
+            .iter()
+            .map(|inverse_bindpose| max_scale(Mat3A::from_mat4(*inverse_bindpose)))
+            .collect();
+
+        BindJoints {
+            joint_parents,
+            origins,
+            scales,
+        }
+    }
+
+    // Return each ancestor of `joint_index`, including itself, along with the
+    // length of the bones between them.
+    fn ancestors(&self, joint_index: usize) -> Vec<(usize, f32)> {
+        let mut ancestors = vec![(joint_index, 0.0)];
+        let mut current = (joint_index, 0.0);
+
+        while let Some(&Some(parent)) = self.joint_parents.get(current.0) {
+            // Guard against cycles.
+            if ancestors.len() > self.joint_parents.len() {
+                break;
+            }
+
+            let length = self.origins[current.0].distance(self.origins[parent]);
+
+            current = (parent, current.1 + length);
+            ancestors.push(current);
+        }
+
+        ancestors
+    }
+
+    // Return the maximum distance between the origins of two joints, or None
+    // if they're not in the same hierarchy.
+    fn max_distance(&self, l: usize, r: usize) -> Option<f32> {
+        let l_ancestors = self.ancestors(l);
+
+        self.ancestors(r)
+            .into_iter()
+            .find_map(|(r_ancestor, r_length)| {
+                l_ancestors
+                    .iter()
+                    .find(|(l_ancestor, _)| *l_ancestor == r_ancestor)
+                    .map(|(_, l_length)| l_length + r_length)
+            })
+    }
+}
+
+// Add each vertex to the AABBs of the joints that influence it, leaving out
+// influences with weights below `min_weight`.
+//
+// Linear blend skinning moves a vertex to the weighted sum of where each joint
+// would put it. Dropping an influence is equivalent to moving its weight to the
+// vertex's strongest joint, plus an offset of the dropped weight times the
+// distance between where the two joints would put the vertex. We bound that
+// distance by the distance from each joint to the vertex and the length of the
+// bones between the joints, and grow each remaining joint's AABB by the total
+// offset.
+//
+// The bone lengths come from the bind pose, so this has the same assumptions
+// as `SkinnedAabbSimplification`.
+pub(crate) fn add_thresholded_influences(
+    optional_aabbs: &mut [Option<Aabb3d>],
+    attributes: &SkinningAttributes,
+    referenced: Option<&[bool]>,
+    inverse_bindposes: &[Mat4],
+    joint_parents: &[Option<usize>],
+    min_weight: f32,
+) {
+    let num_joints = inverse_bindposes.len();
+    let joints = BindJoints::new(joint_parents, inverse_bindposes);
+
+    let mut influences = Vec::<(usize, f32)>::new();
+    let mut kept = Vec::<usize>::new();
+
+    for vertex_index in 0..attributes.positions.len() {
+        if !is_referenced(referenced, vertex_index) {
+            continue;
+        }
+
+        let position = Vec3A::from_array(attributes.positions[vertex_index]);
+
+        influences.clear();
+        influences.extend(
+            (0..attributes.num_influences())
+                .map(|influence_index| attributes.influence(vertex_index, influence_index))
+                .filter(|&(_, joint_weight)| joint_weight > 0.0)
+                .map(|(joint_index, joint_weight)| (joint_index as usize, joint_weight)),
+        );
+
+        for &(joint_index, _) in &influences {
+            // TODO: Replace assert with error?
+
+            assert!(
+                joint_index < num_joints,
+                "Joint index out of range. Joint index = {joint_index}, number of joints = {num_joints}.",
+            );
+        }
+
+        // The strongest joint takes the dropped weights. If there's no joints
+        // above the threshold then keep everything.
+
+        let Some(&(strongest_joint, strongest_weight)) =
+            influences.iter().max_by(|l, r| l.1.total_cmp(&r.1))
+        else {
+            continue;
+        };
+
+        let threshold = if strongest_weight >= min_weight {
+            min_weight
+        } else {
+            0.0
+        };
+
+        kept.clear();
+
+        // The bounds are built from the normalized weights and then scaled by
+        // the weight sum, so the offset is in terms of normalized weights too.
+
+        let weight_sum = influences
+            .iter()
+            .map(|&(_, joint_weight)| joint_weight)
+            .sum::<f32>();
+
+        let mut offset = 0.0;
+
+        for &(joint_index, joint_weight) in &influences {
+            let distance = (joint_weight < threshold)
+                .then(|| joints.max_distance(joint_index, strongest_joint))
+                .flatten()
+                .map(|bone_distance| {
+                    bone_distance
+                        + position.distance(joints.origins[joint_index])
+                        + position.distance(joints.origins[strongest_joint])
+                });
+
+            match distance {
+                Some(distance) => offset += (joint_weight / weight_sum) * distance,
+                None => kept.push(joint_index),
+            }
+        }
+
+        for &joint_index in &kept {
+            let jointspace_position = inverse_bindposes[joint_index].transform_point3a(position);
+            let half_size = Vec3A::splat(offset * joints.scales[joint_index]);
+
+            let vertex_aabb = Aabb3d {
+                min: jointspace_position - half_size,
+                max: jointspace_position + half_size,
+            };
+
+            optional_aabbs[joint_index] = Some(
+                optional_aabbs[joint_index].map_or(vertex_aabb, |aabb| aabb.merge(&vertex_aabb)),
+            );
+        }
+    }
+}
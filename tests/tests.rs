@@ -2,20 +2,41 @@
 mod dev;
 
 use bevy::prelude::*;
+use bevy_asset::RenderAssetUsages;
 use bevy_camera::primitives::{Aabb, MeshAabb};
 use bevy_ecs::system::RunSystemOnce;
-use bevy_math::Vec3A;
-use bevy_mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy_math::{
+    Affine3A, Vec3A,
+    bounding::{Aabb3d, BoundingVolume},
+};
+use bevy_mesh::{
+    Indices, MeshVertexAttribute, PrimitiveTopology, VertexAttributeValues, VertexFormat,
+    skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
+};
 use bevy_mod_skinned_aabb::{
-    SkinnedAabbPluginSettings, create_skinned_aabbs, update_skinned_aabbs,
+    PackedAabb3d, SkinnedAabb, SkinnedAabbAsset, SkinnedAabbCreateError, SkinnedAabbDegraded,
+    SkinnedAabbDiagnostics, SkinnedAabbHysteresis, SkinnedAabbInfluenceAttributes,
+    SkinnedAabbInfluences, SkinnedAabbIssue, SkinnedAabbJointPadding, SkinnedAabbLodSettings,
+    SkinnedAabbPadding, SkinnedAabbPluginSettings, SkinnedAabbSimplification, SkinnedBoundsTarget,
+    SkinnedJointBounds, aabb_transformed_by, create_skinned_aabbs, raycast::SkinnedAabbRaycast,
+    update_skinned_aabbs,
+};
+use bevy_transform::systems::{
+    mark_dirty_trees, propagate_parent_transforms, sync_simple_transforms,
+};
+use core::f32::consts::PI;
+use dev::{
+    RandomSkinnedMeshType, create_and_spawn_random_skinned_mesh, create_dev_world,
+    random_vec3_snorm, skin, spawn_random_mesh_selection, update_random_mesh_animations,
 };
-use dev::{create_dev_world, skin, spawn_random_mesh_selection, update_random_mesh_animations};
+use rand::{SeedableRng, rngs::StdRng};
 
 fn test_against_cpu_skinning(
     query: Query<(&Mesh3d, &SkinnedMesh, &GlobalTransform, &Aabb)>,
     joints: Query<&GlobalTransform>,
     inverse_bindposes_assets: Res<Assets<SkinnedMeshInverseBindposes>>,
     mesh_assets: Res<Assets<Mesh>>,
+    settings: Res<SkinnedAabbPluginSettings>,
 ) {
     assert!(
         query.iter().count() > 0,
@@ -42,10 +63,15 @@ fn test_against_cpu_skinning(
                 let conservative_min = aabb.min();
                 let conservative_max = aabb.max();
 
-                let epsilon = Vec3A::splat(0.001);
+                // Without conservative rounding we need some tolerance for
+                // floating point error.
+                let epsilon = match settings.conservative_rounding {
+                    true => Vec3A::ZERO,
+                    false => Vec3A::splat(0.001),
+                };
 
                 assert!(
-                    conservative_min.cmplt(accurate_min + epsilon).all(),
+                    conservative_min.cmple(accurate_min + epsilon).all(),
                     "Conservative minimum {conservative_min} should not be greater than the accurate minimum {accurate_min}.",
                 );
                 assert!(
@@ -61,11 +87,14 @@ fn test_against_cpu_skinning(
     }
 }
 
-#[test]
-fn test() {
-    let world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+fn test_with_settings(settings: SkinnedAabbPluginSettings) {
+    test_with_spawner(settings, spawn_random_mesh_selection);
+}
 
-    world.run_system_once(spawn_random_mesh_selection).unwrap();
+fn test_with_spawner<M>(settings: SkinnedAabbPluginSettings, spawner: impl IntoSystem<(), (), M>) {
+    let world = &mut create_dev_world(settings);
+
+    world.run_system_once(spawner).unwrap();
     world.run_system_once(create_skinned_aabbs).unwrap();
 
     for _ in 0..100 {
@@ -76,3 +105,2544 @@ fn test() {
         world.run_system_cached(test_against_cpu_skinning).unwrap();
     }
 }
+
+// Create a world with a selection of random meshes and their skinned AABBs.
+fn create_test_world(settings: SkinnedAabbPluginSettings) -> World {
+    let mut world = create_dev_world(settings);
+
+    world.init_resource::<StaticTransformOptimizations>();
+
+    world.run_system_once(spawn_random_mesh_selection).unwrap();
+    world.run_system_once(create_skinned_aabbs).unwrap();
+
+    world
+}
+
+// Step the random animations and propagate the transforms, so that the
+// `GlobalTransform` of each joint is up to date.
+fn update_animations_and_transforms(world: &mut World) {
+    world
+        .run_system_cached(update_random_mesh_animations)
+        .unwrap();
+
+    propagate_transforms(world);
+}
+
+fn propagate_transforms(world: &mut World) {
+    world.run_system_cached(mark_dirty_trees).unwrap();
+    world
+        .run_system_cached(propagate_parent_transforms)
+        .unwrap();
+    world.run_system_cached(sync_simple_transforms).unwrap();
+}
+
+// Return the world-space positions of the vertices of `entity` after CPU
+// skinning.
+fn cpu_skinned_world_positions(world: &mut World, entity: Entity) -> Vec<Vec3A> {
+    world
+        .run_system_once(
+            move |query: Query<(&Mesh3d, &SkinnedMesh, &GlobalTransform)>,
+                  joints: Query<&GlobalTransform>,
+                  inverse_bindposes_assets: Res<Assets<SkinnedMeshInverseBindposes>>,
+                  mesh_assets: Res<Assets<Mesh>>| {
+                let (mesh, skinned_mesh, world_from_entity) = query.get(entity).unwrap();
+
+                let Ok(cpu_skinned_mesh) = skin(
+                    mesh,
+                    skinned_mesh,
+                    world_from_entity,
+                    &mesh_assets,
+                    &inverse_bindposes_assets,
+                    &joints,
+                ) else {
+                    unreachable!("Failed to skin mesh.");
+                };
+
+                let Some(VertexAttributeValues::Float32x3(positions)) =
+                    cpu_skinned_mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+                else {
+                    unreachable!("Missing positions.");
+                };
+
+                positions
+                    .iter()
+                    .map(|&position| {
+                        world_from_entity
+                            .affine()
+                            .transform_point3a(position.into())
+                    })
+                    .collect::<Vec<_>>()
+            },
+        )
+        .unwrap()
+}
+
+// Assert that `aabb` contains every point, with a small tolerance for floating
+// point error.
+fn assert_contains_points(aabb: Aabb3d, points: impl IntoIterator<Item = Vec3A>) {
+    let epsilon = Vec3A::splat(0.001);
+
+    for point in points {
+        assert!(
+            aabb.min.cmple(point + epsilon).all() && aabb.max.cmpge(point - epsilon).all(),
+            "{aabb:?} should contain {point}.",
+        );
+    }
+}
+
+// Return every entity with a `SkinnedMesh`.
+fn skinned_meshes(world: &mut World) -> Vec<Entity> {
+    let meshes = world
+        .query_filtered::<Entity, With<SkinnedMesh>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+
+    assert!(
+        !meshes.is_empty(),
+        "Missing expected components or entities."
+    );
+
+    meshes
+}
+
+// Return an AABB that contains `aabb` after it's transformed.
+fn transformed_aabb(aabb: &Aabb, transform: Affine3A) -> Aabb3d {
+    aabb_transformed_by(
+        PackedAabb3d {
+            min: aabb.min().into(),
+            max: aabb.max().into(),
+        },
+        transform,
+    )
+}
+
+// Assert that `outer` contains `inner`, with the same tolerance as
+// `assert_contains_points`.
+fn assert_contains_aabb(outer: Aabb3d, inner: Aabb3d) {
+    assert_contains_points(outer, [inner.min, inner.max]);
+}
+
+// Create a world with a player that plays two clips, and a mesh with baked
+// bounds for each clip. Returns the world and the mesh.
+#[cfg(feature = "animation")]
+fn create_clip_bounds_world() -> (World, Entity) {
+    use bevy_mod_skinned_aabb::clip::SkinnedAabbClipBounds;
+
+    let mut world = World::default();
+
+    world.init_resource::<Assets<AnimationClip>>();
+    world.init_resource::<Assets<AnimationGraph>>();
+    world.init_resource::<SkinnedAabbPluginSettings>();
+    world.init_resource::<SkinnedAabbDiagnostics>();
+
+    let clips = [
+        world
+            .resource_mut::<Assets<AnimationClip>>()
+            .add(AnimationClip::default()),
+        world
+            .resource_mut::<Assets<AnimationClip>>()
+            .add(AnimationClip::default()),
+    ];
+
+    let (graph, nodes) = AnimationGraph::from_clips(clips.clone());
+    let graph = world.resource_mut::<Assets<AnimationGraph>>().add(graph);
+
+    let mut player = AnimationPlayer::default();
+    player.play(nodes[0]);
+    player.play(nodes[1]);
+
+    let player = world
+        .spawn((
+            player,
+            AnimationGraphHandle(graph),
+            GlobalTransform::from_translation(Vec3::new(1.0, 0.0, 0.0)),
+        ))
+        .id();
+
+    let clip_aabbs = [
+        Aabb3d::new(Vec3::ZERO, Vec3::ONE),
+        Aabb3d::new(Vec3::new(0.0, 2.0, 0.0), Vec3::ONE),
+    ];
+
+    let mesh = world
+        .spawn((
+            Aabb::default(),
+            GlobalTransform::from_translation(Vec3::new(0.0, 0.0, 3.0)),
+            SkinnedAabbClipBounds {
+                player,
+                clips: clips
+                    .iter()
+                    .zip(clip_aabbs)
+                    .map(|(clip, aabb)| (clip.id(), PackedAabb3d::from(aabb)))
+                    .collect(),
+            },
+        ))
+        .id();
+
+    (world, mesh)
+}
+
+// The root joint of a random mesh, its world transform, and each child joint
+// with its bind pose transform relative to the root.
+struct BindSkeleton {
+    root: Entity,
+    world_from_root: Affine3A,
+    children: Vec<(Entity, Affine3A)>,
+}
+
+// Return the skeleton of each random mesh. The random meshes have a root joint
+// whose children are all the other joints.
+fn bind_skeletons(world: &mut World) -> Vec<BindSkeleton> {
+    skinned_meshes(world)
+        .into_iter()
+        .map(|mesh| {
+            let skinned_mesh = world.get::<SkinnedMesh>(mesh).unwrap();
+            let root = skinned_mesh.joints[0];
+            let world_from_root = world.get::<GlobalTransform>(root).unwrap().affine();
+
+            let inverse_bindposes = world
+                .resource::<Assets<SkinnedMeshInverseBindposes>>()
+                .get(&skinned_mesh.inverse_bindposes)
+                .unwrap();
+
+            let children = skinned_mesh
+                .joints
+                .iter()
+                .zip(inverse_bindposes.iter())
+                .skip(1)
+                .map(|(&child, child_from_model)| {
+                    let root_from_child =
+                        Affine3A::from_mat4(inverse_bindposes[0] * child_from_model.inverse());
+
+                    (child, root_from_child)
+                })
+                .collect();
+
+            BindSkeleton {
+                root,
+                world_from_root,
+                children,
+            }
+        })
+        .collect()
+}
+
+// Randomly rotate each joint around its origin, keeping the translation and
+// scale of the bind pose like `test_min_joint_weight`. Simplified AABBs are
+// only conservative for this kind of animation. Sets the `GlobalTransform` of
+// the joints directly.
+fn rotate_skeletons(world: &mut World, skeletons: &[BindSkeleton], rng: &mut StdRng) {
+    let mut random_rotation =
+        || Affine3A::from_quat(Quat::from_scaled_axis(PI * random_vec3_snorm(rng)));
+
+    for skeleton in skeletons {
+        let world_from_root = skeleton.world_from_root * random_rotation();
+
+        *world.get_mut::<GlobalTransform>(skeleton.root).unwrap() = world_from_root.into();
+
+        for &(child, root_from_child) in &skeleton.children {
+            *world.get_mut::<GlobalTransform>(child).unwrap() =
+                (world_from_root * root_from_child * random_rotation()).into();
+        }
+    }
+}
+
+// A `SkinnedBoundsTarget` that stores world-space bounds, like a physics
+// engine might.
+#[derive(Component, Default)]
+struct WorldBounds(Option<Aabb3d>);
+
+impl SkinnedBoundsTarget for WorldBounds {
+    fn skinned_bounds(&self) -> Option<Aabb> {
+        None
+    }
+
+    fn set_skinned_bounds(&mut self, aabb: Aabb, world_from_entity: &GlobalTransform) {
+        self.0 = Some(transformed_aabb(&aabb, world_from_entity.affine()));
+    }
+}
+
+#[test]
+fn test() {
+    test_with_settings(SkinnedAabbPluginSettings::default());
+}
+
+#[test]
+fn test_joint_batching() {
+    use bevy_mod_skinned_aabb::SkinnedAabbBatching;
+
+    // The mesh selection mixes skeletons with 1, 20 and 200 joints, so batches
+    // of 50 joints will group the small skeletons and give the large ones a
+    // batch of their own. A batch size larger than the total joint count
+    // leaves a single batch, which is updated without spawning tasks. The
+    // results should match the sequential update.
+
+    for joints_per_batch in [50, usize::MAX] {
+        let reference_world = &mut create_test_world(SkinnedAabbPluginSettings {
+            parallel: false,
+            ..Default::default()
+        });
+
+        let world = &mut create_test_world(SkinnedAabbPluginSettings {
+            parallel: true,
+            batching: SkinnedAabbBatching::Joints(joints_per_batch),
+            ..Default::default()
+        });
+
+        let mut meshes = skinned_meshes(world);
+
+        meshes.sort();
+
+        assert_eq!(meshes, {
+            let mut reference_meshes = skinned_meshes(reference_world);
+            reference_meshes.sort();
+            reference_meshes
+        });
+
+        for _ in 0..10 {
+            for world in [&mut *reference_world, &mut *world] {
+                update_animations_and_transforms(world);
+                world.run_system_cached(update_skinned_aabbs).unwrap();
+            }
+
+            world.run_system_cached(test_against_cpu_skinning).unwrap();
+
+            for &mesh in &meshes {
+                assert_eq!(
+                    world.get::<Aabb>(mesh),
+                    reference_world.get::<Aabb>(mesh),
+                    "Mesh {mesh} should have the same AABB as the sequential update."
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hysteresis() {
+    test_with_settings(SkinnedAabbPluginSettings {
+        hysteresis: Some(SkinnedAabbHysteresis::default()),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_hysteresis_updates() {
+    let world = &mut create_test_world(SkinnedAabbPluginSettings {
+        hysteresis: Some(SkinnedAabbHysteresis::default()),
+        ..Default::default()
+    });
+
+    propagate_transforms(world);
+    world.run_system_cached(update_skinned_aabbs).unwrap();
+
+    let meshes = skinned_meshes(world);
+
+    // Move the root joint of each mesh by a fraction of its size, or many
+    // times its size. The joints are siblings of the mesh, so this moves the
+    // skinned vertices in entity space.
+
+    let move_root_joints = |world: &mut World, fraction: f32| {
+        for &mesh in &meshes {
+            let size = world.get::<Aabb>(mesh).unwrap().half_extents.max_element();
+            let root_joint = world.get::<SkinnedMesh>(mesh).unwrap().joints[0];
+
+            world
+                .get_mut::<Transform>(root_joint)
+                .unwrap()
+                .translation
+                .x += size * fraction;
+        }
+
+        propagate_transforms(world);
+    };
+
+    let last_changed = |world: &World| {
+        meshes
+            .iter()
+            .map(|&mesh| world.entity(mesh).get_ref::<Aabb>().unwrap().last_changed())
+            .collect::<Vec<_>>()
+    };
+
+    // Small movements stay within the slack, so the `Aabb` isn't changed.
+
+    let before = last_changed(world);
+
+    move_root_joints(world, 0.01);
+    world.run_system_cached(update_skinned_aabbs).unwrap();
+
+    assert_eq!(last_changed(world), before);
+
+    // Large movements escape the `Aabb`, so it's rewritten.
+
+    move_root_joints(world, 10.0);
+    world.run_system_cached(update_skinned_aabbs).unwrap();
+
+    let after = last_changed(world);
+
+    for (&mesh, (before, after)) in meshes.iter().zip(before.iter().zip(after)) {
+        assert_ne!(*before, after, "Mesh {mesh} should have a new `Aabb`.");
+
+        let world_from_entity = world.get::<GlobalTransform>(mesh).unwrap().affine();
+        let aabb = world.get::<Aabb>(mesh).unwrap();
+
+        assert_contains_points(
+            transformed_aabb(aabb, world_from_entity),
+            cpu_skinned_world_positions(world, mesh),
+        );
+    }
+}
+
+#[test]
+fn test_simplification() {
+    let max_aabbs = 4;
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings {
+        simplification: Some(SkinnedAabbSimplification {
+            max_aabbs,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let assets = world.resource::<Assets<SkinnedAabbAsset>>();
+
+    assert!(!assets.is_empty(), "Missing expected assets.");
+
+    for (_, asset) in assets.iter() {
+        assert!(
+            asset.num_aabbs() <= max_aabbs,
+            "Expected at most {max_aabbs} AABBs, found {}.",
+            asset.num_aabbs(),
+        );
+    }
+}
+
+#[test]
+fn test_simplification_against_cpu_skinning() {
+    let world = &mut create_test_world(SkinnedAabbPluginSettings {
+        simplification: Some(SkinnedAabbSimplification {
+            max_aabbs: 4,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    propagate_transforms(world);
+
+    let mut rng = StdRng::seed_from_u64(190427);
+    let skeletons = bind_skeletons(world);
+
+    for _ in 0..100 {
+        rotate_skeletons(world, &skeletons, &mut rng);
+
+        world.run_system_cached(update_skinned_aabbs).unwrap();
+        world.run_system_cached(test_against_cpu_skinning).unwrap();
+    }
+}
+
+#[test]
+fn test_lod() {
+    let lod = SkinnedAabbLodSettings::default();
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings {
+        lod: Some(lod),
+        ..Default::default()
+    });
+
+    let assets = world.resource::<Assets<SkinnedAabbAsset>>();
+
+    assert!(!assets.is_empty(), "Missing expected assets.");
+
+    for (_, asset) in assets.iter() {
+        assert_eq!(asset.num_lods(), 3);
+        assert!(asset.lod(1).0.len() <= lod.reduced_max_aabbs);
+        assert_eq!(asset.lod(2).0.len(), 1);
+    }
+}
+
+#[test]
+fn test_lod_cameras() {
+    let lod = SkinnedAabbLodSettings::default();
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings {
+        lod: Some(lod),
+        ..Default::default()
+    });
+
+    propagate_transforms(world);
+
+    let mut rng = StdRng::seed_from_u64(628413);
+    let skeletons = bind_skeletons(world);
+    let meshes = skinned_meshes(world);
+
+    // Return the entity-space AABB of the joints in a level of detail.
+    let lod_aabb = |world: &World, mesh: Entity, level: usize| {
+        let entity = world.entity(mesh);
+        let skinned_mesh = entity.get::<SkinnedMesh>().unwrap();
+        let entity_from_world = entity.get::<GlobalTransform>().unwrap().affine().inverse();
+
+        let assets = world.resource::<Assets<SkinnedAabbAsset>>();
+        let asset = assets
+            .get(&entity.get::<SkinnedAabb>().unwrap().asset)
+            .unwrap();
+        let (aabbs, aabb_index_to_joint_index) = asset.lod(level);
+
+        aabbs
+            .iter()
+            .zip(aabb_index_to_joint_index)
+            .map(|(&aabb, &joint_index)| {
+                let joint = skinned_mesh.joints[joint_index as usize];
+                let world_from_joint = world.get::<GlobalTransform>(joint).unwrap().affine();
+
+                aabb_transformed_by(aabb, entity_from_world * world_from_joint)
+            })
+            .reduce(|l, r| l.merge(&r))
+            .unwrap()
+    };
+
+    // The meshes are within a few units of the origin, so a camera this far
+    // along the z axis puts them all in the same level.
+
+    let camera = world
+        .spawn((Camera::default(), GlobalTransform::IDENTITY))
+        .id();
+
+    for (camera_z, expected_level) in [
+        (0.5 * lod.reduced_distance, 0),
+        (0.5 * (lod.reduced_distance + lod.single_distance), 1),
+        (2.0 * lod.single_distance, 2),
+    ] {
+        *world.get_mut::<GlobalTransform>(camera).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(0.0, 0.0, camera_z));
+
+        for _ in 0..20 {
+            rotate_skeletons(world, &skeletons, &mut rng);
+
+            world.run_system_cached(update_skinned_aabbs).unwrap();
+            world.run_system_cached(test_against_cpu_skinning).unwrap();
+
+            for &mesh in &meshes {
+                let distance = world
+                    .get::<GlobalTransform>(mesh)
+                    .unwrap()
+                    .translation()
+                    .distance(Vec3::new(0.0, 0.0, camera_z));
+
+                assert_eq!(lod.lod(distance), expected_level);
+
+                let aabb = world.get::<Aabb>(mesh).unwrap();
+                let expected = lod_aabb(world, mesh, expected_level);
+
+                let error = (aabb.min() - expected.min)
+                    .abs()
+                    .max((aabb.max() - expected.max).abs());
+
+                assert!(
+                    error.max_element() < 0.001,
+                    "Mesh {mesh} should use level {expected_level}. Expected {expected:?}, found {aabb:?}."
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "animation")]
+#[test]
+fn test_clip_bounds() {
+    use bevy_mod_skinned_aabb::clip::{SkinnedAabbClipBounds, update_skinned_aabbs_from_clips};
+
+    let (mut world, mesh) = create_clip_bounds_world();
+
+    world
+        .run_system_once(update_skinned_aabbs_from_clips)
+        .unwrap();
+
+    // The union of both clips, translated from player space to entity space.
+    let expected = Aabb::from_min_max(Vec3::new(0.0, -1.0, -4.0), Vec3::new(2.0, 3.0, -2.0));
+    let actual = *world.get::<Aabb>(mesh).unwrap();
+
+    assert!(
+        (actual.min() - expected.min()).abs().max_element() < 0.0001
+            && (actual.max() - expected.max()).abs().max_element() < 0.0001,
+        "Expected {expected:?}, found {actual:?}.",
+    );
+
+    // The entity's padding is added to the clip bounds.
+
+    world.entity_mut(mesh).insert(SkinnedAabbPadding(0.5));
+
+    world
+        .run_system_once(update_skinned_aabbs_from_clips)
+        .unwrap();
+
+    let padded = *world.get::<Aabb>(mesh).unwrap();
+
+    assert!(
+        (padded.half_extents - expected.half_extents - Vec3A::splat(0.5))
+            .abs()
+            .max_element()
+            < 0.0001,
+        "Expected {expected:?} padded by 0.5, found {padded:?}.",
+    );
+
+    // If a playing clip wasn't baked then the `Aabb` is left alone and the
+    // issue is reported.
+
+    world
+        .get_mut::<SkinnedAabbClipBounds>(mesh)
+        .unwrap()
+        .clips
+        .retain(|_, aabb| aabb.min[1] < 0.0);
+
+    *world.get_mut::<Aabb>(mesh).unwrap() = expected;
+
+    world
+        .run_system_once(update_skinned_aabbs_from_clips)
+        .unwrap();
+
+    assert_eq!(*world.get::<Aabb>(mesh).unwrap(), expected);
+    assert_eq!(
+        world.resource::<SkinnedAabbDiagnostics>().issues,
+        [(mesh, SkinnedAabbIssue::MissingClipBounds)],
+    );
+}
+
+#[cfg(feature = "animation")]
+#[test]
+fn test_bake_clip_bounds() {
+    use bevy::animation::{AnimatedBy, AnimationTargetId, animate_targets, animated_field};
+    use bevy_mod_skinned_aabb::clip::{SkinnedAabbClipBounds, bake_skinned_aabb_clip_bounds};
+
+    let samples_per_second = 10.0;
+
+    let mut app = App::new();
+
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        bevy::time::TimePlugin,
+        AssetPlugin::default(),
+        AnimationPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<SkinnedMeshInverseBindposes>()
+    .init_asset::<SkinnedAabbAsset>()
+    .init_asset::<StandardMaterial>()
+    .init_resource::<SkinnedAabbPluginSettings>()
+    .init_resource::<SkinnedAabbInfluences>()
+    .init_resource::<SkinnedAabbJointPadding>()
+    .init_resource::<StaticTransformOptimizations>();
+
+    app.world_mut()
+        .run_system_once(spawn_random_mesh_selection)
+        .unwrap();
+
+    // Make the base entity of the first mesh the player, and animate each of
+    // its joints by name.
+
+    let world = app.world_mut();
+    let mesh = skinned_meshes(world)[0];
+    let player = world.get::<ChildOf>(mesh).unwrap().parent();
+    let joints = world.get::<SkinnedMesh>(mesh).unwrap().joints.clone();
+
+    // Each clip rotates the joints around a different axis, so the bounds of
+    // the clips are different.
+
+    let clips = [Vec3::X, Vec3::Z].map(|axis| {
+        let mut clip = AnimationClip::default();
+
+        for (joint_index, &joint) in joints.iter().enumerate() {
+            let name = Name::new(format!("joint_{joint_index}"));
+            let target_id = AnimationTargetId::from_name(&name);
+
+            let keyframes = (0..=4).map(|key| {
+                let angle = (key as f32) * (joint_index as f32 + 1.0) * 0.5;
+
+                (key as f32 * 0.5, Quat::from_axis_angle(axis, angle))
+            });
+
+            clip.add_curve_to_target(
+                target_id,
+                AnimatableCurve::new(
+                    animated_field!(Transform::rotation),
+                    AnimatableKeyframeCurve::new(keyframes).unwrap(),
+                ),
+            );
+
+            world
+                .entity_mut(joint)
+                .insert((name, target_id, AnimatedBy(player)));
+        }
+
+        world.resource_mut::<Assets<AnimationClip>>().add(clip)
+    });
+
+    let (graph, nodes) = AnimationGraph::from_clips(clips.clone());
+    let graph = world.resource_mut::<Assets<AnimationGraph>>().add(graph);
+
+    world
+        .entity_mut(player)
+        .insert((AnimationPlayer::default(), AnimationGraphHandle(graph)));
+
+    // The graph is prepared for evaluation by the animation plugin after the
+    // asset event is sent.
+
+    app.update();
+    app.update();
+
+    // Play one clip part way through, so we can check it's restored.
+
+    let world = app.world_mut();
+
+    world
+        .get_mut::<AnimationPlayer>(player)
+        .unwrap()
+        .play(nodes[1])
+        .set_speed(0.5)
+        .set_seek_time(0.25);
+
+    bake_skinned_aabb_clip_bounds(world, player, samples_per_second).unwrap();
+
+    let animation_player = world.get::<AnimationPlayer>(player).unwrap();
+    let playing = animation_player.playing_animations().collect::<Vec<_>>();
+
+    assert_eq!(playing.len(), 1);
+    assert_eq!(*playing[0].0, nodes[1]);
+    assert_eq!(playing[0].1.speed(), 0.5);
+    assert_eq!(playing[0].1.seek_time(), 0.25);
+
+    let clip_bounds = world.get::<SkinnedAabbClipBounds>(mesh).unwrap().clone();
+
+    assert_eq!(clip_bounds.player, player);
+    assert_eq!(clip_bounds.clips.len(), clips.len());
+
+    let clip_aabbs = clips
+        .iter()
+        .map(|clip| Aabb3d::from(clip_bounds.clips[&clip.id()]))
+        .collect::<Vec<_>>();
+
+    assert_ne!(clip_aabbs[0], clip_aabbs[1]);
+
+    // The bounds of each clip should contain the vertices at each sample.
+
+    for ((&node, clip), clip_aabb) in nodes.iter().zip(&clips).zip(clip_aabbs) {
+        let duration = world
+            .resource::<Assets<AnimationClip>>()
+            .get(clip)
+            .unwrap()
+            .duration();
+
+        let num_samples = (duration * samples_per_second).ceil() as usize;
+
+        for sample_index in 0..=num_samples {
+            let time = duration * (sample_index as f32) / (num_samples as f32);
+
+            world
+                .get_mut::<AnimationPlayer>(player)
+                .unwrap()
+                .stop_all()
+                .play(node)
+                .set_seek_time(time);
+
+            world.run_system_once(animate_targets).unwrap();
+            world.run_system_once(mark_dirty_trees).unwrap();
+            world.run_system_once(propagate_parent_transforms).unwrap();
+            world.run_system_once(sync_simple_transforms).unwrap();
+
+            let player_from_world = world
+                .get::<GlobalTransform>(player)
+                .unwrap()
+                .affine()
+                .inverse();
+
+            assert_contains_points(
+                clip_aabb,
+                cpu_skinned_world_positions(world, mesh)
+                    .into_iter()
+                    .map(|position| player_from_world.transform_point3a(position)),
+            );
+        }
+    }
+
+    // Baking again with conservative rounding should grow each clip's bounds.
+
+    world
+        .resource_mut::<SkinnedAabbPluginSettings>()
+        .conservative_rounding = true;
+
+    bake_skinned_aabb_clip_bounds(world, player, samples_per_second).unwrap();
+
+    let rounded_bounds = world.get::<SkinnedAabbClipBounds>(mesh).unwrap();
+
+    for clip in &clips {
+        let aabb = Aabb3d::from(clip_bounds.clips[&clip.id()]);
+        let rounded = Aabb3d::from(rounded_bounds.clips[&clip.id()]);
+
+        assert!(rounded.contains(&aabb) && rounded != aabb);
+    }
+}
+
+fn spawn_unnormalized_meshes(
+    mut commands: Commands,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    mut inverse_bindposes_assets: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+) {
+    let mut rng = StdRng::seed_from_u64(381274);
+
+    for num_joints in [1, 20, 200] {
+        // Place the meshes away from the origin, since unnormalized weights
+        // scale the vertices towards the world origin.
+
+        let base_entity = commands
+            .spawn(Transform::from_translation(Vec3::new(10.0, 5.0, 0.0)))
+            .id();
+
+        let mesh_transform = Transform::from_translation(random_vec3_snorm(&mut rng));
+
+        create_and_spawn_random_skinned_mesh(
+            &mut commands,
+            &mut mesh_assets,
+            &mut inverse_bindposes_assets,
+            &mut rng,
+            base_entity,
+            mesh_transform,
+            RandomSkinnedMeshType::SoftUnnormalized { num_tris: 100 },
+            num_joints,
+        )
+        .ok();
+    }
+}
+
+#[test]
+fn test_unnormalized_weights() {
+    test_with_spawner(
+        SkinnedAabbPluginSettings::default(),
+        spawn_unnormalized_meshes,
+    );
+}
+
+#[test]
+fn test_unweighted_vertices() {
+    let world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+
+    world.run_system_once(spawn_random_mesh_selection).unwrap();
+
+    // Remove the weights of one vertex from each mesh, and place it outside
+    // the mesh so that only the unweighted vertex handling can include it.
+
+    let unweighted_position = Vec3::new(0.0, 100.0, 0.0);
+
+    for (_, mesh) in world.resource_mut::<Assets<Mesh>>().iter_mut() {
+        if let Some(VertexAttributeValues::Float32x4(joint_weights)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_JOINT_WEIGHT)
+        {
+            joint_weights[0] = [0.0; 4];
+        }
+
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            positions[0] = unweighted_position.to_array();
+        }
+    }
+
+    world.run_system_once(create_skinned_aabbs).unwrap();
+    world.run_system_once(update_skinned_aabbs).unwrap();
+
+    let mut query = world.query_filtered::<&Aabb, With<SkinnedMesh>>();
+
+    assert!(
+        query.iter(world).count() > 0,
+        "Missing expected components or entities."
+    );
+
+    for aabb in query.iter(world) {
+        assert!(
+            aabb.max().y >= unweighted_position.y,
+            "Expected {aabb:?} to contain the unweighted vertex {unweighted_position}.",
+        );
+    }
+}
+
+#[test]
+fn test_referenced_vertices_only() {
+    let world = &mut create_dev_world(SkinnedAabbPluginSettings {
+        referenced_vertices_only: true,
+        ..Default::default()
+    });
+
+    world.run_system_once(spawn_random_mesh_selection).unwrap();
+
+    // Index every triangle except the first, and move the first triangle's
+    // vertices far outside the mesh.
+
+    let unreferenced_position = Vec3::new(0.0, 100.0, 0.0);
+
+    for (_, mesh) in world.resource_mut::<Assets<Mesh>>().iter_mut() {
+        let num_vertices = mesh.count_vertices() as u32;
+
+        mesh.insert_indices(Indices::U32((3..num_vertices).collect()));
+
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            positions[0..3].fill(unreferenced_position.to_array());
+        }
+    }
+
+    world.run_system_once(create_skinned_aabbs).unwrap();
+    world.run_system_once(update_skinned_aabbs).unwrap();
+
+    let mut query = world.query_filtered::<&Aabb, With<SkinnedMesh>>();
+
+    assert!(
+        query.iter(world).count() > 0,
+        "Missing expected components or entities."
+    );
+
+    for aabb in query.iter(world) {
+        assert!(
+            aabb.max().y < unreferenced_position.y,
+            "Expected {aabb:?} to exclude the unreferenced vertex {unreferenced_position}.",
+        );
+    }
+}
+
+#[test]
+fn test_influence_attributes() {
+    const EXTRA_INFLUENCES: SkinnedAabbInfluenceAttributes = SkinnedAabbInfluenceAttributes {
+        joint_indices: MeshVertexAttribute::new(
+            "Test_JointIndex1",
+            683195107,
+            VertexFormat::Uint16x4,
+        ),
+        joint_weights: MeshVertexAttribute::new(
+            "Test_JointWeight1",
+            683195108,
+            VertexFormat::Float32x4,
+        ),
+    };
+
+    let reference_world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+    let world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+
+    world.insert_resource(SkinnedAabbInfluences(vec![
+        SkinnedAabbInfluenceAttributes::STANDARD,
+        EXTRA_INFLUENCES,
+    ]));
+
+    reference_world
+        .run_system_once(spawn_random_mesh_selection)
+        .unwrap();
+    world.run_system_once(spawn_random_mesh_selection).unwrap();
+
+    // Move all but the first influence of each vertex to the extra attributes.
+    // The result should match the unmodified meshes.
+
+    for (_, mesh) in world.resource_mut::<Assets<Mesh>>().iter_mut() {
+        let (
+            Some(VertexAttributeValues::Uint16x4(joint_indices)),
+            Some(VertexAttributeValues::Float32x4(joint_weights)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX).cloned(),
+            mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT).cloned(),
+        )
+        else {
+            continue;
+        };
+
+        let first = |i: [u16; 4], w: [f32; 4]| ([i[0], 0, 0, 0], [w[0], 0.0, 0.0, 0.0]);
+        let rest = |i: [u16; 4], w: [f32; 4]| ([i[1], i[2], i[3], 0], [w[1], w[2], w[3], 0.0]);
+
+        let (first_indices, first_weights): (Vec<_>, Vec<_>) = joint_indices
+            .iter()
+            .zip(&joint_weights)
+            .map(|(&i, &w)| first(i, w))
+            .unzip();
+
+        let (rest_indices, rest_weights): (Vec<_>, Vec<_>) = joint_indices
+            .iter()
+            .zip(&joint_weights)
+            .map(|(&i, &w)| rest(i, w))
+            .unzip();
+
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(first_indices),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, first_weights);
+        mesh.insert_attribute(
+            EXTRA_INFLUENCES.joint_indices,
+            VertexAttributeValues::Uint16x4(rest_indices),
+        );
+        mesh.insert_attribute(EXTRA_INFLUENCES.joint_weights, rest_weights);
+    }
+
+    for world in [&mut *reference_world, &mut *world] {
+        world.run_system_once(create_skinned_aabbs).unwrap();
+        world.run_system_once(update_skinned_aabbs).unwrap();
+    }
+
+    let expected = reference_world
+        .query_filtered::<&Aabb, With<SkinnedMesh>>()
+        .iter(reference_world)
+        .copied()
+        .collect::<Vec<_>>();
+
+    let actual = world
+        .query_filtered::<&Aabb, With<SkinnedMesh>>()
+        .iter(world)
+        .copied()
+        .collect::<Vec<_>>();
+
+    assert!(
+        !expected.is_empty(),
+        "Missing expected components or entities."
+    );
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_min_joint_weight() {
+    test_min_joint_weight_with_sum(1.0);
+}
+
+// Weights that add up to less than one scale the bounds down, so the offsets
+// of dropped influences must be scaled up to match.
+#[test]
+fn test_min_joint_weight_unnormalized() {
+    test_min_joint_weight_with_sum(0.25);
+
+    test_with_spawner(
+        SkinnedAabbPluginSettings {
+            min_joint_weight: 0.2,
+            ..Default::default()
+        },
+        spawn_unnormalized_meshes,
+    );
+}
+
+fn test_min_joint_weight_with_sum(weight_sum: f32) {
+    let world = &mut create_dev_world(SkinnedAabbPluginSettings {
+        min_joint_weight: 0.05 * weight_sum,
+        ..Default::default()
+    });
+
+    let mut rng = StdRng::seed_from_u64(598271);
+
+    // A mesh near the root joint with a small weight on a distant child joint.
+
+    let bone_length = 10.0;
+    let num_vertices = 300;
+
+    let positions = (0..num_vertices)
+        .map(|_| 0.5 * random_vec3_snorm(&mut rng))
+        .collect::<Vec<_>>();
+
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_JOINT_INDEX,
+        VertexAttributeValues::Uint16x4(vec![[0, 1, 0, 0]; num_vertices]),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_JOINT_WEIGHT,
+        vec![[0.99 * weight_sum, 0.01 * weight_sum, 0.0, 0.0]; num_vertices],
+    );
+
+    let inverse_bindposes = SkinnedMeshInverseBindposes::from(vec![
+        Mat4::IDENTITY,
+        Mat4::from_translation(Vec3::new(0.0, -bone_length, 0.0)),
+    ]);
+
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(mesh);
+    let inverse_bindposes = world
+        .resource_mut::<Assets<SkinnedMeshInverseBindposes>>()
+        .add(inverse_bindposes);
+
+    let root = world.spawn(GlobalTransform::IDENTITY).id();
+    let child = world.spawn((GlobalTransform::IDENTITY, ChildOf(root))).id();
+
+    world.spawn((
+        Mesh3d(mesh),
+        SkinnedMesh {
+            inverse_bindposes,
+            joints: vec![root, child],
+        },
+        GlobalTransform::IDENTITY,
+        Aabb::default(),
+    ));
+
+    world.run_system_once(create_skinned_aabbs).unwrap();
+
+    // The child joint should have been dropped entirely.
+
+    for (_, asset) in world.resource::<Assets<SkinnedAabbAsset>>().iter() {
+        assert_eq!(asset.num_aabbs(), 1);
+    }
+
+    // Randomly rotate the joints while keeping the bone length.
+
+    for _ in 0..100 {
+        let root_rotation = Quat::from_scaled_axis(PI * random_vec3_snorm(&mut rng));
+        let child_rotation = Quat::from_scaled_axis(PI * random_vec3_snorm(&mut rng));
+
+        let world_from_root = Transform::from_rotation(root_rotation);
+        let world_from_child = world_from_root
+            * Transform::from_translation(Vec3::new(0.0, bone_length, 0.0))
+            * Transform::from_rotation(child_rotation);
+
+        *world.get_mut::<GlobalTransform>(root).unwrap() = world_from_root.into();
+        *world.get_mut::<GlobalTransform>(child).unwrap() = world_from_child.into();
+
+        world.run_system_cached(update_skinned_aabbs).unwrap();
+        world.run_system_cached(test_against_cpu_skinning).unwrap();
+    }
+}
+
+#[test]
+fn test_conservative_rounding() {
+    let world = &mut create_test_world(SkinnedAabbPluginSettings {
+        conservative_rounding: true,
+        ..Default::default()
+    });
+
+    // Move the meshes far from the origin so that rounding errors are larger
+    // than the gaps between the vertices and the bounds.
+
+    let mut roots = world.query_filtered::<&mut Transform, Without<ChildOf>>();
+
+    for mut transform in roots.iter_mut(world) {
+        transform.translation += Vec3::splat(10000.0);
+    }
+
+    for _ in 0..100 {
+        update_animations_and_transforms(world);
+
+        world.run_system_cached(update_skinned_aabbs).unwrap();
+        world.run_system_cached(test_against_cpu_skinning).unwrap();
+    }
+}
+
+#[test]
+fn test_invalid_transforms() {
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+    let meshes = skinned_meshes(world);
+
+    let (zero_scale_mesh, nan_joint_mesh) = (meshes[0], meshes[1]);
+
+    *world.get_mut::<GlobalTransform>(zero_scale_mesh).unwrap() =
+        GlobalTransform::from_scale(Vec3::ZERO);
+
+    // The first joint isn't skinned, so use the last.
+    let nan_joint = *world
+        .get::<SkinnedMesh>(nan_joint_mesh)
+        .unwrap()
+        .joints
+        .last()
+        .unwrap();
+
+    *world.get_mut::<GlobalTransform>(nan_joint).unwrap() =
+        GlobalTransform::from_translation(Vec3::NAN);
+
+    world.run_system_once(update_skinned_aabbs).unwrap();
+
+    let issues = &world.resource::<SkinnedAabbDiagnostics>().issues;
+
+    assert!(issues.contains(&(zero_scale_mesh, SkinnedAabbIssue::InvalidEntityTransform)));
+    assert!(issues.contains(&(nan_joint_mesh, SkinnedAabbIssue::InvalidJointTransform)));
+
+    for aabb in world
+        .query_filtered::<&Aabb, With<SkinnedMesh>>()
+        .iter(world)
+    {
+        assert!(
+            aabb.center.is_finite() && aabb.half_extents.is_finite(),
+            "Expected {aabb:?} to be finite.",
+        );
+    }
+}
+
+#[cfg(feature = "animation")]
+#[test]
+fn test_clip_bounds_invalid_transforms() {
+    use bevy_mod_skinned_aabb::clip::update_skinned_aabbs_from_clips;
+
+    let (mut world, mesh) = create_clip_bounds_world();
+
+    let valid = *world.get::<GlobalTransform>(mesh).unwrap();
+
+    world
+        .run_system_once(update_skinned_aabbs_from_clips)
+        .unwrap();
+
+    let expected = *world.get::<Aabb>(mesh).unwrap();
+
+    // Scaling the entity to zero or making it non-finite leaves the `Aabb`
+    // alone and reports the issue.
+
+    for invalid in [
+        GlobalTransform::from_scale(Vec3::ZERO),
+        GlobalTransform::from_translation(Vec3::NAN),
+    ] {
+        world.entity_mut(mesh).insert(invalid);
+
+        world
+            .run_system_once(update_skinned_aabbs_from_clips)
+            .unwrap();
+
+        assert_eq!(*world.get::<Aabb>(mesh).unwrap(), expected);
+        assert_eq!(
+            world.resource::<SkinnedAabbDiagnostics>().issues,
+            [(mesh, SkinnedAabbIssue::InvalidEntityTransform)]
+        );
+    }
+
+    // Fixing the transform clears the issue.
+
+    world.entity_mut(mesh).insert(valid);
+
+    world
+        .run_system_once(update_skinned_aabbs_from_clips)
+        .unwrap();
+
+    assert!(world.resource::<SkinnedAabbDiagnostics>().issues.is_empty());
+}
+
+#[test]
+fn test_mismatched_joints() {
+    for allow_mismatched_joints in [false, true] {
+        let world = &mut create_dev_world(SkinnedAabbPluginSettings {
+            allow_mismatched_joints,
+            ..Default::default()
+        });
+
+        world.run_system_once(spawn_random_mesh_selection).unwrap();
+
+        let mesh = world
+            .query_filtered::<Entity, With<SkinnedMesh>>()
+            .iter(world)
+            .nth(1)
+            .unwrap();
+
+        let num_joints = {
+            let mut skinned_mesh = world.get_mut::<SkinnedMesh>(mesh).unwrap();
+            skinned_mesh.joints.pop();
+            skinned_mesh.joints.len()
+        };
+
+        world.run_system_once(create_skinned_aabbs).unwrap();
+        world.run_system_once(update_skinned_aabbs).unwrap();
+
+        let degraded = world.get::<SkinnedAabbDegraded>(mesh).unwrap();
+
+        assert_eq!(
+            degraded.0,
+            SkinnedAabbCreateError::MismatchedJointCount {
+                joints: num_joints,
+                inverse_bindposes: num_joints + 1,
+            }
+        );
+
+        assert_eq!(
+            world.get::<SkinnedAabb>(mesh).is_some(),
+            allow_mismatched_joints
+        );
+    }
+}
+
+#[test]
+fn test_padding() {
+    let reference_world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+    let world = &mut create_dev_world(SkinnedAabbPluginSettings {
+        padding: 0.25,
+        ..Default::default()
+    });
+
+    world.insert_resource(SkinnedAabbJointPadding(vec![
+        ("hat".into(), 2.0),
+        ("cape_*".into(), 0.5),
+    ]));
+
+    // Give every joint of the first mesh a name that matches the pattern, and
+    // override the padding of the second mesh. The reference world only uses
+    // the joint names, since its settings have no joint padding.
+
+    for (world, padded) in [(&mut *reference_world, false), (&mut *world, true)] {
+        world.run_system_once(spawn_random_mesh_selection).unwrap();
+
+        let mut meshes = world
+            .query_filtered::<Entity, With<SkinnedMesh>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+
+        meshes.sort();
+
+        let joints = world.get::<SkinnedMesh>(meshes[0]).unwrap().joints.clone();
+
+        for (joint_index, joint) in joints.into_iter().enumerate() {
+            world
+                .entity_mut(joint)
+                .insert(Name::new(format!("cape_{joint_index}")));
+        }
+
+        if padded {
+            world.entity_mut(meshes[1]).insert(SkinnedAabbPadding(1.0));
+        }
+
+        world.run_system_once(create_skinned_aabbs).unwrap();
+        world.run_system_once(update_skinned_aabbs).unwrap();
+    }
+
+    // Adding components changes the query order, so match the entities by
+    // their spawn order instead. Both worlds spawn the same entities.
+
+    let mut meshes = world
+        .query_filtered::<Entity, With<SkinnedMesh>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+
+    meshes.sort();
+
+    assert!(meshes.len() > 2, "Missing expected components or entities.");
+
+    for (index, &mesh) in meshes.iter().enumerate() {
+        let padding = match index {
+            0 => 0.75,
+            1 => 1.0,
+            _ => 0.25,
+        };
+
+        let expected = reference_world.get::<Aabb>(mesh).unwrap();
+        let actual = world.get::<Aabb>(mesh).unwrap();
+        let difference = actual.half_extents - expected.half_extents;
+
+        assert!(
+            (difference - Vec3A::splat(padding)).abs().max_element() < 0.0001,
+            "Expected padding of {padding}, found {difference}.",
+        );
+    }
+}
+
+#[test]
+fn test_joint_padding_with_simplification() {
+    // The padding has to be large, since the simplified bounds are loose
+    // enough to hide small amounts.
+    let joint_padding = SkinnedAabbJointPadding(vec![("cape_*".into(), 1000.0)]);
+
+    // Every level of detail is simplified, so the padded joints are always
+    // merged into their unpadded ancestors. A camera at the origin picks the
+    // level, since every mesh is further away than the distances.
+
+    let simplified_settings = [
+        SkinnedAabbPluginSettings {
+            simplification: Some(SkinnedAabbSimplification {
+                max_aabbs: 1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        SkinnedAabbPluginSettings {
+            lod: Some(SkinnedAabbLodSettings {
+                reduced_max_aabbs: 2,
+                reduced_distance: 0.0,
+                single_distance: f32::MAX,
+            }),
+            ..Default::default()
+        },
+        SkinnedAabbPluginSettings {
+            lod: Some(SkinnedAabbLodSettings {
+                single_distance: 0.0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    ];
+
+    for settings in simplified_settings {
+        let reference_world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+        let world = &mut create_dev_world(settings);
+
+        // Pad every joint except the roots of each skeleton.
+
+        for world in [&mut *reference_world, &mut *world] {
+            world.insert_resource(joint_padding.clone());
+            world.init_resource::<StaticTransformOptimizations>();
+            world.spawn((Camera::default(), GlobalTransform::default()));
+            world.run_system_once(spawn_random_mesh_selection).unwrap();
+
+            let joints = world
+                .query::<&SkinnedMesh>()
+                .iter(world)
+                .flat_map(|skinned_mesh| skinned_mesh.joints.clone())
+                .collect::<Vec<_>>();
+
+            for &joint in &joints {
+                let parent = world.get::<ChildOf>(joint).map(ChildOf::parent);
+
+                if parent.is_some_and(|parent| joints.contains(&parent)) {
+                    world
+                        .entity_mut(joint)
+                        .insert(Name::new(format!("cape_{joint}")));
+                }
+            }
+
+            world.run_system_once(create_skinned_aabbs).unwrap();
+        }
+
+        let mut meshes = skinned_meshes(world);
+
+        meshes.sort();
+
+        for _ in 0..10 {
+            for world in [&mut *reference_world, &mut *world] {
+                update_animations_and_transforms(world);
+                world.run_system_cached(update_skinned_aabbs).unwrap();
+            }
+
+            for &mesh in &meshes {
+                let expected = reference_world.get::<Aabb>(mesh).unwrap();
+                let actual = world.get::<Aabb>(mesh).unwrap();
+
+                assert_contains_aabb(
+                    transformed_aabb(actual, Affine3A::IDENTITY),
+                    transformed_aabb(expected, Affine3A::IDENTITY),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_joint_bounds() {
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    // Name the last joint of each mesh, since the first joint can have no
+    // vertices of its own.
+
+    let meshes = world
+        .query::<(Entity, &SkinnedMesh)>()
+        .iter(world)
+        .map(|(entity, skinned_mesh)| (entity, *skinned_mesh.joints.last().unwrap()))
+        .collect::<Vec<_>>();
+
+    assert!(
+        !meshes.is_empty(),
+        "Missing expected components or entities."
+    );
+
+    for &(_, joint) in &meshes {
+        world.entity_mut(joint).insert(Name::new("Head"));
+    }
+
+    world
+        .run_system_once(move |bounds: SkinnedJointBounds| {
+            for &(entity, joint) in &meshes {
+                let skinned_aabb = bounds.query.get(entity).unwrap().0;
+                let asset = bounds.assets.get(&skinned_aabb.asset).unwrap();
+
+                assert_eq!(bounds.iter(entity).count(), asset.num_aabbs());
+                assert!(bounds.named(entity, "Tail").is_none());
+
+                let head = bounds.named(entity, "Head").unwrap();
+
+                assert_eq!(head.joint_entity, joint);
+
+                let aabb_index = asset
+                    .aabb_index_to_joint_index
+                    .iter()
+                    .position(|&joint_index| joint_index == head.joint_index)
+                    .unwrap();
+
+                let expected = aabb_transformed_by(
+                    asset.aabb(aabb_index),
+                    bounds.joints.get(joint).unwrap().affine(),
+                );
+
+                assert_eq!(head.world_aabb().min, expected.min);
+                assert_eq!(head.world_aabb().max, expected.max);
+            }
+        })
+        .unwrap();
+}
+
+#[test]
+fn test_raycast() {
+    use bevy_math::bounding::BoundingVolume;
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+    world.run_system_once(update_skinned_aabbs).unwrap();
+
+    world
+        .run_system_once(
+            |raycast: SkinnedAabbRaycast, meshes: Query<Entity, With<SkinnedAabb>>| {
+                assert!(
+                    !meshes.is_empty(),
+                    "Missing expected components or entities."
+                );
+
+                for entity in &meshes {
+                    // Aim a ray at the center of a joint box from outside.
+
+                    let target = raycast.joint_bounds.iter(entity).next().unwrap();
+                    let center = Vec3::from(target.world_aabb().center());
+                    let ray = Ray3d::new(center + Vec3::new(0.0, 0.0, 1000.0), Dir3::NEG_Z);
+
+                    let hits = raycast.cast_ray(ray);
+
+                    assert!(hits.iter().any(|hit| hit.entity == entity
+                        && hit.joint_entity == target.joint_entity
+                        && hit.joint_index == target.joint_index));
+
+                    assert!(hits.is_sorted_by(|l, r| l.distance <= r.distance));
+                    assert!(hits.iter().all(|hit| hit.distance > 900.0));
+
+                    assert_eq!(
+                        raycast.cast_ray_nearest(ray).map(|hit| hit.distance),
+                        hits.first().map(|hit| hit.distance),
+                    );
+
+                    // The same ray pointing the other way should miss.
+
+                    let reversed = Ray3d::new(ray.origin, Dir3::Z);
+
+                    assert!(raycast.cast_ray(reversed).is_empty());
+                }
+            },
+        )
+        .unwrap();
+}
+
+// Rays that run along the face of an axis-aligned joint box should hit it,
+// even though the ray doesn't move along that axis.
+#[test]
+fn test_raycast_along_face() {
+    let world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+
+    let positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z, Vec3::ONE];
+    let num_vertices = positions.len();
+
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_JOINT_INDEX,
+        VertexAttributeValues::Uint16x4(vec![[0, 0, 0, 0]; num_vertices]),
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_JOINT_WEIGHT,
+        vec![[1.0, 0.0, 0.0, 0.0]; num_vertices],
+    );
+
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(mesh);
+    let inverse_bindposes = world
+        .resource_mut::<Assets<SkinnedMeshInverseBindposes>>()
+        .add(SkinnedMeshInverseBindposes::from(vec![Mat4::IDENTITY]));
+
+    let joint = world.spawn(GlobalTransform::IDENTITY).id();
+
+    let entity = world
+        .spawn((
+            Mesh3d(mesh),
+            SkinnedMesh {
+                inverse_bindposes,
+                joints: vec![joint],
+            },
+            GlobalTransform::IDENTITY,
+            Aabb::default(),
+        ))
+        .id();
+
+    world.run_system_once(create_skinned_aabbs).unwrap();
+    world.run_system_once(update_skinned_aabbs).unwrap();
+
+    // The box is the unit cube, so each ray starts on the plane of one of its
+    // faces.
+
+    let rays = [
+        Ray3d::new(Vec3::new(0.0, 0.5, 10.0), Dir3::NEG_Z),
+        Ray3d::new(Vec3::new(1.0, 0.5, 10.0), Dir3::NEG_Z),
+        Ray3d::new(Vec3::new(0.5, 0.0, -9.0), Dir3::Z),
+        Ray3d::new(Vec3::new(10.0, 1.0, 0.0), Dir3::NEG_X),
+    ];
+
+    for ray in rays {
+        let hit = world
+            .run_system_once(move |raycast: SkinnedAabbRaycast| raycast.cast_ray_nearest(ray))
+            .unwrap();
+
+        assert!(
+            hit.is_some_and(|hit| hit.entity == entity && (hit.distance - 9.0).abs() < 0.0001),
+            "Expected {ray:?} to hit at distance 9, found {hit:?}.",
+        );
+    }
+}
+
+#[cfg(feature = "picking")]
+#[test]
+fn test_picking() {
+    use bevy::{
+        ecs::message::Messages,
+        picking::{
+            backend::{
+                PointerHits,
+                ray::{RayId, RayMap},
+            },
+            pointer::PointerId,
+        },
+    };
+    use bevy_math::bounding::BoundingVolume;
+    use bevy_mod_skinned_aabb::picking::{
+        SkinnedAabbPickingHits, SkinnedAabbPickingSettings, update_hits,
+    };
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    world.init_resource::<SkinnedAabbPickingSettings>();
+    world.init_resource::<SkinnedAabbPickingHits>();
+    world.init_resource::<Messages<PointerHits>>();
+    world.init_resource::<RayMap>();
+
+    world.run_system_once(update_skinned_aabbs).unwrap();
+
+    let camera = world.spawn(Camera::default()).id();
+
+    // Aim the mouse at a joint of the first mesh.
+
+    let target = world
+        .run_system_once(
+            |raycast: SkinnedAabbRaycast, meshes: Query<Entity, With<SkinnedAabb>>| {
+                let entity = meshes.iter().min().unwrap();
+
+                (entity, raycast.joint_bounds.iter(entity).next().unwrap())
+            },
+        )
+        .unwrap();
+
+    let center = Vec3::from(target.1.world_aabb().center());
+    let ray = Ray3d::new(center + Vec3::new(0.0, 0.0, 1000.0), Dir3::NEG_Z);
+
+    world
+        .resource_mut::<RayMap>()
+        .map
+        .insert(RayId::new(camera, PointerId::Mouse), ray);
+
+    world.run_system_once(update_hits).unwrap();
+
+    let pointer_hits = world
+        .resource_mut::<Messages<PointerHits>>()
+        .drain()
+        .collect::<Vec<_>>();
+
+    assert_eq!(pointer_hits.len(), 1);
+
+    let (_, hit_data) = pointer_hits[0]
+        .picks
+        .iter()
+        .find(|(entity, _)| *entity == target.0)
+        .unwrap();
+
+    assert_eq!(hit_data.camera, camera);
+
+    // Another joint of the same mesh might be in front of the target, so only
+    // check that the hit joint belongs to the mesh and matches the pick.
+
+    let hit = *world
+        .resource::<SkinnedAabbPickingHits>()
+        .get(PointerId::Mouse, target.0)
+        .unwrap();
+
+    let joints = &world.get::<SkinnedMesh>(target.0).unwrap().joints;
+
+    assert_eq!(joints[hit.joint_index as usize], hit.joint_entity);
+    assert_eq!(hit.distance, hit_data.depth);
+}
+
+#[cfg(feature = "picking")]
+#[test]
+fn test_picking_visibility() {
+    use bevy::{
+        camera::visibility::{InheritedVisibility, RenderLayers},
+        ecs::message::Messages,
+        picking::{
+            backend::{
+                PointerHits,
+                ray::{RayId, RayMap},
+            },
+            pointer::PointerId,
+        },
+    };
+    use bevy_math::bounding::BoundingVolume;
+    use bevy_mod_skinned_aabb::picking::{
+        SkinnedAabbPickingHits, SkinnedAabbPickingSettings, SkinnedAabbPickingVisibility,
+        update_hits,
+    };
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    world.insert_resource(SkinnedAabbPickingSettings {
+        visibility: SkinnedAabbPickingVisibility::Visible,
+        ..Default::default()
+    });
+    world.init_resource::<SkinnedAabbPickingHits>();
+    world.init_resource::<Messages<PointerHits>>();
+    world.init_resource::<RayMap>();
+
+    world.run_system_once(update_skinned_aabbs).unwrap();
+
+    let camera = world.spawn(Camera::default()).id();
+
+    // Aim a pointer at a joint of each of three meshes, then hide the first
+    // and move the second to another layer.
+
+    let targets = world
+        .run_system_once(
+            |raycast: SkinnedAabbRaycast, meshes: Query<Entity, With<SkinnedAabb>>| {
+                let mut entities = meshes.iter().collect::<Vec<_>>();
+
+                entities.sort();
+
+                entities
+                    .into_iter()
+                    .take(3)
+                    .map(|entity| (entity, raycast.joint_bounds.iter(entity).next().unwrap()))
+                    .collect::<Vec<_>>()
+            },
+        )
+        .unwrap();
+
+    world
+        .entity_mut(targets[0].0)
+        .insert(InheritedVisibility::HIDDEN);
+
+    world
+        .entity_mut(targets[1].0)
+        .insert(RenderLayers::layer(1));
+
+    for (index, (_, bounds)) in targets.iter().enumerate() {
+        let center = Vec3::from(bounds.world_aabb().center());
+        let ray = Ray3d::new(center + Vec3::new(0.0, 0.0, 1000.0), Dir3::NEG_Z);
+
+        world
+            .resource_mut::<RayMap>()
+            .map
+            .insert(RayId::new(camera, PointerId::Touch(index as u64)), ray);
+    }
+
+    let picked = |world: &mut World| {
+        world.run_system_once(update_hits).unwrap();
+
+        let hits = world.resource::<SkinnedAabbPickingHits>();
+
+        targets
+            .iter()
+            .enumerate()
+            .map(|(index, (entity, _))| hits.get(PointerId::Touch(index as u64), *entity).is_some())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(picked(world), [false, false, true]);
+
+    // The hidden mesh can be hit if visibility is ignored, and the mesh on
+    // another layer can be hit by a camera on that layer.
+
+    world
+        .resource_mut::<SkinnedAabbPickingSettings>()
+        .visibility = SkinnedAabbPickingVisibility::Any;
+
+    world
+        .entity_mut(camera)
+        .insert(RenderLayers::from_layers(&[0, 1]));
+
+    assert_eq!(picked(world), [true, true, true]);
+}
+
+#[test]
+fn test_overlap() {
+    use bevy::ecs::message::Messages;
+    use bevy_mod_skinned_aabb::{
+        SkinnedJointBound,
+        overlap::{
+            SkinnedAabbHitbox, SkinnedAabbHitboxOverlap, joint_bounds_overlap, send_hitbox_overlaps,
+        },
+    };
+    use core::f32::consts::{FRAC_PI_4, SQRT_2};
+
+    // Two cubes where the second is rotated so that its face points at the
+    // first cube's corner. Their world-space AABBs overlap for a range of
+    // distances where the cubes don't.
+
+    let cube = |world_from_joint| SkinnedJointBound {
+        joint_index: 0,
+        joint_entity: Entity::PLACEHOLDER,
+        aabb: PackedAabb3d {
+            min: Vec3::splat(-1.0),
+            max: Vec3::splat(1.0),
+        },
+        world_from_joint,
+    };
+
+    let diagonal = Vec3::new(1.0, 1.0, 0.0).normalize();
+    let separation = 1.0 + SQRT_2;
+
+    let a = cube(Affine3A::IDENTITY);
+
+    for (distance, expected) in [(separation - 0.1, true), (separation + 0.1, false)] {
+        let b = cube(Affine3A::from_rotation_translation(
+            Quat::from_rotation_z(FRAC_PI_4),
+            diagonal * distance,
+        ));
+
+        assert!(a.world_aabb().min.cmple(b.world_aabb().max).all());
+        assert_eq!(joint_bounds_overlap(&a, &b), expected);
+        assert_eq!(joint_bounds_overlap(&b, &a), expected);
+    }
+
+    // The meshes start overlapping since the transforms haven't been
+    // propagated. Move one mesh and its joints far away from the others.
+
+    let world = &mut create_dev_world(SkinnedAabbPluginSettings::default());
+
+    world.init_resource::<Messages<SkinnedAabbHitboxOverlap>>();
+    world.run_system_once(spawn_random_mesh_selection).unwrap();
+
+    let meshes = skinned_meshes(world);
+
+    assert!(meshes.len() > 2, "Missing expected components or entities.");
+
+    let moved = meshes[0];
+    let moved_joints = world.get::<SkinnedMesh>(moved).unwrap().joints.clone();
+    let far_away = GlobalTransform::from_translation(Vec3::new(1000.0, 0.0, 0.0));
+
+    for entity in moved_joints.into_iter().chain([moved]) {
+        world.entity_mut(entity).insert(far_away);
+    }
+
+    for &mesh in &meshes {
+        world.entity_mut(mesh).insert(SkinnedAabbHitbox);
+    }
+
+    world.run_system_once(create_skinned_aabbs).unwrap();
+    world.run_system_once(update_skinned_aabbs).unwrap();
+    world.run_system_once(send_hitbox_overlaps).unwrap();
+
+    let overlaps = world
+        .resource_mut::<Messages<SkinnedAabbHitboxOverlap>>()
+        .drain()
+        .collect::<Vec<_>>();
+
+    assert!(!overlaps.is_empty());
+
+    for overlap in overlaps {
+        assert!(!overlap.joints.is_empty());
+        assert_ne!(overlap.entity_a, moved);
+        assert_ne!(overlap.entity_b, moved);
+    }
+}
+
+#[test]
+fn test_broadphase() {
+    use bevy_math::bounding::{
+        Aabb3d, BoundingSphere, BoundingVolume, IntersectsVolume, RayCast3d,
+    };
+    use bevy_mod_skinned_aabb::broadphase::{
+        SkinnedAabbBroadphase, update_skinned_aabb_broadphase,
+    };
+
+    let mut rng = StdRng::seed_from_u64(1234);
+    let world = &mut World::default();
+
+    // Scatter boxes of various sizes, including some that are too big to go
+    // in the grid.
+
+    let entries = (0..500)
+        .map(|index| {
+            let center = 50.0 * random_vec3_snorm(&mut rng);
+            let size = match index % 50 {
+                0 => 40.0,
+                _ => 2.0,
+            };
+
+            let half_size = size * (random_vec3_snorm(&mut rng).abs() + 0.1);
+
+            (world.spawn_empty().id(), Aabb3d::new(center, half_size))
+        })
+        .collect::<Vec<_>>();
+
+    let mut broadphase = SkinnedAabbBroadphase::new(3.0);
+
+    broadphase.rebuild(entries.iter().copied());
+
+    let sorted = |mut entities: Vec<Entity>| {
+        entities.sort();
+        entities
+    };
+
+    for _ in 0..100 {
+        let center = 60.0 * random_vec3_snorm(&mut rng);
+        let half_size = 10.0 * random_vec3_snorm(&mut rng).abs();
+
+        let aabb = Aabb3d::new(center, half_size);
+        let expected = entries
+            .iter()
+            .filter(|(_, entry)| entry.intersects(&aabb))
+            .map(|&(entity, _)| entity)
+            .collect();
+
+        assert_eq!(sorted(broadphase.query_aabb(aabb)), sorted(expected));
+
+        let sphere = BoundingSphere::new(center, half_size.x);
+        let expected = entries
+            .iter()
+            .filter(|(_, entry)| entry.intersects(&sphere))
+            .map(|&(entity, _)| entity)
+            .collect();
+
+        assert_eq!(sorted(broadphase.query_sphere(sphere)), sorted(expected));
+
+        let direction = Dir3::new(random_vec3_snorm(&mut rng)).unwrap_or(Dir3::X);
+        let ray = Ray3d::new(center, direction);
+        let max_distance = 80.0;
+        let ray_cast = RayCast3d::from_ray(ray, max_distance);
+
+        let mut expected = entries
+            .iter()
+            .filter_map(|&(entity, entry)| Some((entity, ray_cast.aabb_intersection_at(&entry)?)))
+            .collect::<Vec<_>>();
+
+        // Hits at the same distance can be in any order.
+
+        let by_distance =
+            |l: &(Entity, f32), r: &(Entity, f32)| l.1.total_cmp(&r.1).then(l.0.cmp(&r.0));
+        let mut actual = broadphase.query_ray(ray, max_distance);
+
+        assert!(actual.is_sorted_by(|l, r| l.1 <= r.1));
+
+        actual.sort_by(by_distance);
+        expected.sort_by(by_distance);
+
+        assert_eq!(actual, expected);
+    }
+
+    // Spread a few boxes far apart, so that a query covering them spans far
+    // more cells than are occupied.
+
+    let spread = [-1.0e6, 0.0, 1.0e6]
+        .map(|x| {
+            (
+                world.spawn_empty().id(),
+                Aabb3d::new(Vec3::new(x, x, x), Vec3::ONE),
+            )
+        })
+        .to_vec();
+
+    let mut broadphase = SkinnedAabbBroadphase::new(1.0);
+
+    broadphase.rebuild(spread.iter().copied());
+
+    assert_eq!(
+        sorted(broadphase.query_aabb(Aabb3d::new(Vec3::ZERO, Vec3::splat(2.0e6)))),
+        sorted(spread.iter().map(|&(entity, _)| entity).collect()),
+    );
+    assert_eq!(
+        sorted(broadphase.query_aabb(Aabb3d::new(Vec3::splat(5.0e5), Vec3::splat(5.0e5)))),
+        sorted(vec![spread[1].0, spread[2].0]),
+    );
+
+    // Rays along the faces of a box should hit it.
+
+    for ray in [
+        Ray3d::new(Vec3::new(-1.0, 0.0, 10.0), Dir3::NEG_Z),
+        Ray3d::new(Vec3::new(0.0, 1.0, 10.0), Dir3::NEG_Z),
+        Ray3d::new(Vec3::new(10.0, 0.0, -1.0), Dir3::NEG_X),
+        Ray3d::new(Vec3::new(10.0, 1.0, -1.0), Dir3::NEG_X),
+    ] {
+        assert_eq!(broadphase.query_ray(ray, 100.0), [(spread[1].0, 9.0)]);
+    }
+
+    // Check that the system fills in the broadphase from the mesh bounds.
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    world.init_resource::<SkinnedAabbBroadphase>();
+    world.run_system_once(update_skinned_aabbs).unwrap();
+    world
+        .run_system_once(update_skinned_aabb_broadphase)
+        .unwrap();
+
+    let meshes = world
+        .query_filtered::<Entity, With<SkinnedAabb>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+
+    let broadphase = world.resource::<SkinnedAabbBroadphase>();
+
+    assert!(
+        !meshes.is_empty(),
+        "Missing expected components or entities."
+    );
+    assert_eq!(broadphase.entries().len(), meshes.len());
+
+    for &(entity, aabb) in broadphase.entries() {
+        assert!(broadphase.query_aabb(aabb).contains(&entity));
+        assert!(
+            broadphase
+                .query_sphere(BoundingSphere::new(aabb.center(), 0.0))
+                .contains(&entity)
+        );
+    }
+}
+
+#[test]
+fn test_broadphase_frustum() {
+    use bevy_camera::primitives::Frustum;
+    use bevy_mod_skinned_aabb::broadphase::SkinnedAabbBroadphase;
+
+    let world = &mut World::default();
+
+    // A camera at the origin looking down the negative z axis, with a 90
+    // degree field of view.
+
+    let frustum =
+        Frustum::from_clip_from_world(&Mat4::perspective_infinite_reverse_rh(PI / 2.0, 1.0, 0.1));
+
+    let visible = [
+        // In front of the camera.
+        Aabb3d::new(Vec3::new(0.0, 0.0, -10.0), Vec3::ONE),
+        // Crossing the near plane.
+        Aabb3d::new(Vec3::ZERO, Vec3::ONE),
+        // Partly inside the right side of the frustum.
+        Aabb3d::new(Vec3::new(11.5, 0.0, -10.0), Vec3::ONE),
+        // Far away. The projection has no far plane.
+        Aabb3d::new(Vec3::new(0.0, 0.0, -1.0e6), Vec3::ONE),
+        // So large that the number of cells it covers overflows.
+        Aabb3d::new(Vec3::ZERO, Vec3::splat(1.0e30)),
+    ]
+    .map(|aabb| (world.spawn_empty().id(), aabb));
+
+    let hidden = [
+        // Behind the camera.
+        Aabb3d::new(Vec3::new(0.0, 0.0, 10.0), Vec3::ONE),
+        // Outside the sides of the frustum.
+        Aabb3d::new(Vec3::new(20.0, 0.0, -10.0), Vec3::ONE),
+        Aabb3d::new(Vec3::new(0.0, -20.0, -10.0), Vec3::ONE),
+    ]
+    .map(|aabb| (world.spawn_empty().id(), aabb));
+
+    let mut broadphase = SkinnedAabbBroadphase::new(3.0);
+
+    broadphase.rebuild(visible.iter().chain(&hidden).copied());
+
+    let mut actual = broadphase.query_frustum(&frustum);
+    let mut expected = visible.map(|(entity, _)| entity).to_vec();
+
+    actual.sort();
+    expected.sort();
+
+    assert_eq!(actual, expected);
+
+    // The huge AABB should still be found by other queries.
+
+    assert!(
+        broadphase
+            .query_aabb(Aabb3d::new(Vec3::new(100.0, 0.0, 0.0), Vec3::ONE))
+            .contains(&visible[4].0)
+    );
+}
+
+#[test]
+fn test_global_aabb() {
+    use bevy_mod_skinned_aabb::GlobalSkinnedAabb;
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+    let meshes = skinned_meshes(world);
+
+    for &mesh in &meshes {
+        world.entity_mut(mesh).insert(GlobalSkinnedAabb::default());
+    }
+
+    for _ in 0..100 {
+        update_animations_and_transforms(world);
+
+        world.run_system_cached(update_skinned_aabbs).unwrap();
+
+        for &mesh in &meshes {
+            let positions = cpu_skinned_world_positions(world, mesh);
+
+            let entity = world.entity(mesh);
+            let global_aabb = entity.get::<GlobalSkinnedAabb>().unwrap().0.unwrap();
+            let aabb = entity.get::<Aabb>().unwrap();
+            let world_from_entity = entity.get::<GlobalTransform>().unwrap().affine();
+
+            assert_contains_points(global_aabb, positions);
+
+            // Should be no looser than transforming the entity-space AABB.
+            assert_contains_aabb(transformed_aabb(aabb, world_from_entity), global_aabb);
+        }
+    }
+}
+
+#[test]
+fn test_bounds_target() {
+    use bevy_mod_skinned_aabb::update_skinned_bounds;
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    // Give half the meshes the custom target instead of an `Aabb`.
+
+    let meshes = skinned_meshes(world);
+
+    assert!(meshes.len() > 1, "Missing expected components or entities.");
+
+    for &mesh in meshes.iter().step_by(2) {
+        world
+            .entity_mut(mesh)
+            .remove::<Aabb>()
+            .insert(WorldBounds::default());
+    }
+
+    world.run_system_once(update_skinned_aabbs).unwrap();
+    world
+        .run_system_once(update_skinned_bounds::<WorldBounds>)
+        .unwrap();
+
+    for (index, &mesh) in meshes.iter().enumerate() {
+        let entity = world.entity(mesh);
+
+        match index % 2 {
+            0 => assert!(entity.get::<WorldBounds>().unwrap().0.is_some()),
+            _ => assert_ne!(entity.get::<Aabb>().unwrap().half_extents, Vec3A::ZERO),
+        }
+    }
+}
+
+#[test]
+fn test_bounds_plugin_order() {
+    use bevy_mod_skinned_aabb::{SkinnedAabbPlugin, SkinnedBoundsPlugin};
+
+    for bounds_first in [false, true] {
+        let mut app = App::new();
+
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+
+        if bounds_first {
+            app.add_plugins(SkinnedBoundsPlugin::<WorldBounds>::default())
+                .add_plugins(SkinnedAabbPlugin);
+        } else {
+            app.add_plugins(SkinnedAabbPlugin)
+                .add_plugins(SkinnedBoundsPlugin::<WorldBounds>::default());
+        }
+
+        app.finish();
+    }
+}
+
+#[test]
+#[should_panic(expected = "SkinnedBoundsPlugin requires SkinnedAabbPlugin")]
+fn test_bounds_plugin_without_aabb_plugin() {
+    use bevy_mod_skinned_aabb::SkinnedBoundsPlugin;
+
+    App::new()
+        .add_plugins((
+            TaskPoolPlugin::default(),
+            AssetPlugin::default(),
+            SkinnedBoundsPlugin::<WorldBounds>::default(),
+        ))
+        .finish();
+}
+
+#[test]
+fn test_skinned_aabb_root() {
+    use bevy_math::bounding::BoundingVolume;
+    use bevy_mod_skinned_aabb::root::{SkinnedAabbRoot, update_skinned_aabb_roots};
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    // The base entities are the parents of the meshes and joints. Give them a
+    // rotation and non-uniform scale so the root space differs from the world.
+
+    let bases = world
+        .query_filtered::<Entity, (With<Children>, Without<ChildOf>)>()
+        .iter(world)
+        .collect::<Vec<_>>();
+
+    assert!(
+        !bases.is_empty(),
+        "Missing expected components or entities."
+    );
+
+    for (index, &base) in bases.iter().enumerate() {
+        let mut base = world.entity_mut(base);
+        let mut transform = base.get_mut::<Transform>().unwrap();
+
+        transform.rotation = Quat::from_rotation_y(index as f32);
+        transform.scale = Vec3::new(1.0, 2.0, 0.5);
+
+        base.insert(SkinnedAabbRoot::default());
+    }
+
+    for _ in 0..100 {
+        update_animations_and_transforms(world);
+
+        world.run_system_cached(update_skinned_aabbs).unwrap();
+        world.run_system_cached(update_skinned_aabb_roots).unwrap();
+
+        for &base in &bases {
+            let root_aabb = world.get::<SkinnedAabbRoot>(base).unwrap().0.unwrap();
+            let root_from_world = world
+                .get::<GlobalTransform>(base)
+                .unwrap()
+                .affine()
+                .inverse();
+
+            let meshes = world
+                .query::<(Entity, &Aabb, &GlobalTransform, &ChildOf)>()
+                .iter(world)
+                .filter(|(_, _, _, child_of)| child_of.parent() == base)
+                .map(|(mesh, aabb, world_from_mesh, _)| (mesh, *aabb, world_from_mesh.affine()))
+                .collect::<Vec<_>>();
+
+            let mut transformed_aabbs: Option<Aabb3d> = None;
+
+            for (mesh, aabb, world_from_mesh) in meshes {
+                let positions = cpu_skinned_world_positions(world, mesh);
+
+                assert_contains_points(
+                    root_aabb,
+                    positions
+                        .into_iter()
+                        .map(|position| root_from_world.transform_point3a(position)),
+                );
+
+                let transformed = transformed_aabb(&aabb, root_from_world * world_from_mesh);
+
+                transformed_aabbs = Some(match transformed_aabbs {
+                    Some(merged) => merged.merge(&transformed),
+                    None => transformed,
+                });
+            }
+
+            // Should be no looser than merging the transformed entity-space
+            // AABBs.
+            assert_contains_aabb(transformed_aabbs.unwrap(), root_aabb);
+        }
+    }
+}
+
+#[test]
+fn test_skinned_aabb_attachment() {
+    use bevy_mod_skinned_aabb::root::{
+        SkinnedAabbAttachment, SkinnedAabbRoot, update_skinned_aabb_roots,
+    };
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    // Attach a box far from the mesh to the last joint of each mesh, and make
+    // the mesh's parent the root.
+
+    let meshes = world
+        .query::<(&SkinnedMesh, &ChildOf)>()
+        .iter(world)
+        .map(|(skinned_mesh, child_of)| (*skinned_mesh.joints.last().unwrap(), child_of.parent()))
+        .collect::<Vec<_>>();
+
+    assert!(
+        !meshes.is_empty(),
+        "Missing expected components or entities."
+    );
+
+    let attachments = meshes
+        .into_iter()
+        .map(|(joint, root)| {
+            world.entity_mut(root).insert(SkinnedAabbRoot::default());
+
+            let attachment = world
+                .spawn((
+                    Transform::from_xyz(10.0, 0.0, 0.0).with_scale(Vec3::splat(2.0)),
+                    Aabb::from_min_max(Vec3::splat(-1.0), Vec3::splat(1.0)),
+                    SkinnedAabbAttachment,
+                    ChildOf(joint),
+                ))
+                .id();
+
+            (attachment, root)
+        })
+        .collect::<Vec<_>>();
+
+    update_animations_and_transforms(world);
+
+    world.run_system_cached(update_skinned_aabbs).unwrap();
+    world.run_system_cached(update_skinned_aabb_roots).unwrap();
+
+    for (attachment, root) in attachments {
+        let root_aabb = world.get::<SkinnedAabbRoot>(root).unwrap().0.unwrap();
+        let world_from_root = world.get::<GlobalTransform>(root).unwrap().affine();
+        let world_from_attachment = world.get::<GlobalTransform>(attachment).unwrap().affine();
+        let aabb = world.get::<Aabb>(attachment).unwrap();
+
+        assert_contains_aabb(
+            root_aabb,
+            transformed_aabb(aabb, world_from_root.inverse() * world_from_attachment),
+        );
+    }
+}
+
+#[test]
+fn test_swept_aabb() {
+    use bevy_mod_skinned_aabb::{PreviousSkinnedAabb, SweptSkinnedAabb};
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+    let meshes = skinned_meshes(world);
+
+    for &mesh in &meshes {
+        world.entity_mut(mesh).insert(SweptSkinnedAabb::default());
+    }
+
+    let mut previous_positions = vec![Vec::new(); meshes.len()];
+
+    for _ in 0..100 {
+        update_animations_and_transforms(world);
+
+        world.run_system_cached(update_skinned_aabbs).unwrap();
+
+        for (&mesh, previous) in meshes.iter().zip(&mut previous_positions) {
+            let current = cpu_skinned_world_positions(world, mesh);
+
+            let entity = world.entity(mesh);
+            let swept = entity.get::<SweptSkinnedAabb>().unwrap().0.unwrap();
+            let previous_aabb = entity.get::<PreviousSkinnedAabb>().unwrap().0.unwrap();
+
+            // The previous AABB has been updated to the current one.
+            assert_contains_points(previous_aabb, current.iter().copied());
+
+            assert_contains_points(swept, current.iter().chain(previous.iter()).copied());
+
+            *previous = current;
+        }
+    }
+}
+
+#[test]
+fn test_swept_aabb_with_bounds_target() {
+    use bevy_mod_skinned_aabb::{GlobalSkinnedAabb, SweptSkinnedAabb, update_skinned_bounds};
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+    let meshes = skinned_meshes(world);
+
+    // Give each mesh a second target as well as its `Aabb`, so that both
+    // update systems see the world-space components.
+
+    for &mesh in &meshes {
+        world.entity_mut(mesh).insert((
+            WorldBounds::default(),
+            GlobalSkinnedAabb::default(),
+            SweptSkinnedAabb::default(),
+        ));
+    }
+
+    // The world-space components belong to the `Aabb` update, so the other
+    // target's update leaves them alone.
+
+    update_animations_and_transforms(world);
+
+    world
+        .run_system_cached(update_skinned_bounds::<WorldBounds>)
+        .unwrap();
+
+    for &mesh in &meshes {
+        let entity = world.entity(mesh);
+
+        assert!(entity.get::<WorldBounds>().unwrap().0.is_some());
+        assert!(entity.get::<GlobalSkinnedAabb>().unwrap().0.is_none());
+        assert!(entity.get::<SweptSkinnedAabb>().unwrap().0.is_none());
+    }
+
+    let mut previous_positions = vec![Vec::new(); meshes.len()];
+
+    for frame in 0..100 {
+        // Move the meshes far enough that the previous and current bounds
+        // don't overlap.
+        for mut transform in world
+            .query_filtered::<&mut Transform, Without<ChildOf>>()
+            .iter_mut(world)
+        {
+            transform.translation.x += 10.0;
+        }
+
+        update_animations_and_transforms(world);
+
+        // The systems aren't ordered relative to each other, so try both.
+        if frame % 2 == 0 {
+            world.run_system_cached(update_skinned_aabbs).unwrap();
+            world
+                .run_system_cached(update_skinned_bounds::<WorldBounds>)
+                .unwrap();
+        } else {
+            world
+                .run_system_cached(update_skinned_bounds::<WorldBounds>)
+                .unwrap();
+            world.run_system_cached(update_skinned_aabbs).unwrap();
+        }
+
+        for (&mesh, previous) in meshes.iter().zip(&mut previous_positions) {
+            let current = cpu_skinned_world_positions(world, mesh);
+
+            let entity = world.entity(mesh);
+            let world_bounds = entity.get::<WorldBounds>().unwrap().0.unwrap();
+            let global_aabb = entity.get::<GlobalSkinnedAabb>().unwrap().0.unwrap();
+            let swept = entity.get::<SweptSkinnedAabb>().unwrap().0.unwrap();
+
+            assert_contains_points(world_bounds, current.iter().copied());
+            assert_contains_points(global_aabb, current.iter().copied());
+            assert_contains_points(swept, current.iter().chain(previous.iter()).copied());
+
+            *previous = current;
+        }
+    }
+}
+
+#[test]
+fn test_screen_rect() {
+    use bevy_camera::{CameraProjection, PerspectiveProjection, RenderTargetInfo};
+    use bevy_mod_skinned_aabb::screen::{SkinnedAabbScreen, SkinnedAabbScreenRect};
+
+    let world = &mut create_test_world(SkinnedAabbPluginSettings::default());
+
+    // Add meshes with unnormalized weights, which the joint boxes alone don't
+    // cover.
+
+    world.run_system_once(spawn_unnormalized_meshes).unwrap();
+    world.run_system_once(create_skinned_aabbs).unwrap();
+
+    // Set up the cameras manually, since there's no render target to calculate
+    // the projection from. The second camera looks at the unnormalized meshes.
+
+    let cameras = [Vec3::ZERO, Vec3::new(10.0, 5.0, 0.0)].map(|target| {
+        let mut camera = Camera::default();
+
+        camera.computed.clip_from_view = PerspectiveProjection {
+            aspect_ratio: 800.0 / 600.0,
+            ..Default::default()
+        }
+        .get_clip_from_view();
+
+        camera.computed.target_info = Some(RenderTargetInfo {
+            physical_size: UVec2::new(800, 600),
+            scale_factor: 1.0,
+        });
+
+        world
+            .spawn((
+                camera,
+                Transform::from_translation(target + Vec3::new(0.0, 3.0, 15.0))
+                    .looking_at(target, Vec3::Y),
+            ))
+            .id()
+    });
+
+    let viewport = Rect::new(0.0, 0.0, 800.0, 600.0);
+
+    let screen_rects = |world: &mut World, camera: Entity| {
+        world
+            .run_system_once(
+                move |query: Query<Entity, With<SkinnedAabb>>, screen: SkinnedAabbScreen| {
+                    query
+                        .iter()
+                        .map(|entity| (entity, screen.screen_rect(camera, entity)))
+                        .collect::<Vec<(Entity, Option<SkinnedAabbScreenRect>)>>()
+                },
+            )
+            .unwrap()
+    };
+
+    for _ in 0..10 {
+        update_animations_and_transforms(world);
+
+        for camera in cameras {
+            let rects = screen_rects(world, camera);
+
+            assert!(
+                rects.iter().any(|(_, rect)| rect.is_some()),
+                "Expected some meshes to be on screen."
+            );
+
+            let (camera_component, world_from_camera) = world
+                .query::<(&Camera, &GlobalTransform)>()
+                .get(world, camera)
+                .unwrap();
+
+            let (camera_component, world_from_camera) =
+                (camera_component.clone(), *world_from_camera);
+
+            for (entity, rect) in rects {
+                let positions = cpu_skinned_world_positions(world, entity);
+
+                let epsilon = 0.01;
+
+                for position in positions {
+                    let position = Vec3::from(position);
+
+                    let Ok(viewport_position) =
+                        camera_component.world_to_viewport(&world_from_camera, position)
+                    else {
+                        continue;
+                    };
+
+                    if !viewport.contains(viewport_position) {
+                        continue;
+                    }
+
+                    let Some(rect) = rect else {
+                        panic!("Visible vertex of a mesh that's not on screen.");
+                    };
+
+                    let depth = -world_from_camera
+                        .affine()
+                        .inverse()
+                        .transform_point3(position)
+                        .z;
+
+                    assert!(rect.rect.inflate(epsilon).contains(viewport_position));
+                    assert!(rect.min_depth <= depth + epsilon);
+                    assert!(rect.max_depth >= depth - epsilon);
+                }
+            }
+        }
+    }
+
+    // Padding grows the rectangle of meshes that are fully on screen.
+
+    let unpadded = screen_rects(world, cameras[0]);
+
+    for &(entity, _) in &unpadded {
+        world.entity_mut(entity).insert(SkinnedAabbPadding(0.1));
+    }
+
+    let padded = screen_rects(world, cameras[0]);
+    let mut num_checked = 0;
+
+    for ((_, unpadded), (_, padded)) in unpadded.iter().zip(&padded) {
+        let Some(unpadded) = unpadded.filter(|rect| {
+            viewport.inflate(-50.0).contains(rect.rect.min)
+                && viewport.inflate(-50.0).contains(rect.rect.max)
+        }) else {
+            continue;
+        };
+
+        let padded = padded.unwrap();
+
+        assert!(padded.rect.min.cmplt(unpadded.rect.min).all());
+        assert!(padded.rect.max.cmpgt(unpadded.rect.max).all());
+        assert!(padded.min_depth < unpadded.min_depth);
+        assert!(padded.max_depth > unpadded.max_depth);
+
+        num_checked += 1;
+    }
+
+    assert!(num_checked > 0);
+}